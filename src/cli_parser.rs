@@ -1,4 +1,7 @@
-use clap::{Arg, Command};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{value_parser, Arg, ArgAction, Command};
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect};
 use regex::Regex;
 
@@ -97,11 +100,112 @@ pub fn cli() -> Command {
             ]),
             Command::new("unset").about("Unset proxy"),
             Command::new("show").about("Show current proxy"),
-            Command::new("auto-apply").about("Apply proxy automatically based on network"),
-            Command::new("setup").about("Setup auto-proxy"),
+            Command::new("status").about("Show the auto-apply daemon's status"),
+            Command::new("connections")
+                .about("List NetworkManager connections and the proxy each carries"),
+            Command::new("connect")
+                .about("Tunnel stdin and stdout to a host through the active proxy, for SSH")
+                .args([
+                    Arg::new("host").help("Host to connect to").required(true),
+                    Arg::new("port")
+                        .help("Port to connect to")
+                        .value_parser(value_parser!(u16))
+                        .required(true),
+                ]),
+            Command::new("pin")
+                .about("Activate a profile and suspend automatic selection")
+                .args([
+                    Arg::new("profile")
+                        .help("Name of the proxy profile, or direct")
+                        .required(true),
+                    Arg::new("for")
+                        .help("Unpin automatically after this long, e.g. 2h or 1h30m")
+                        .long("for")
+                        .value_parser(parse_duration),
+                    Arg::new("until-network-change")
+                        .help("Unpin automatically when the network changes")
+                        .long("until-network-change")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("for"),
+                ]),
+            Command::new("unpin").about("Remove the pin and resume automatic selection"),
+            Command::new("auto-apply")
+                .about("Apply proxy automatically based on network")
+                .visible_alias("daemon")
+                .args([
+                    Arg::new("once")
+                        .help("Evaluate the current network and apply once, then exit")
+                        .long("once")
+                        .action(ArgAction::SetTrue),
+                    Arg::new("debounce")
+                        .help("Seconds to wait for the network to settle before applying")
+                        .long("debounce")
+                        .value_parser(value_parser!(u64))
+                        .default_value("3"),
+                    Arg::new("gradle-project")
+                        .help("Also manage gradle.properties in this project directory")
+                        .long("gradle-project")
+                        .value_name("DIR")
+                        .value_parser(value_parser!(PathBuf))
+                        .action(ArgAction::Append),
+                ]),
+            Command::new("setup").about("Setup auto-proxy").args([
+                Arg::new("systemd")
+                    .help("Install a systemd user service running the daemon")
+                    .long("systemd")
+                    .action(ArgAction::SetTrue),
+                Arg::new("dispatcher")
+                    .help("Install a NetworkManager dispatcher script running auto-apply --once")
+                    .long("dispatcher")
+                    .action(ArgAction::SetTrue),
+                Arg::new("uninstall")
+                    .help("Remove the systemd user service and dispatcher script")
+                    .long("uninstall")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all(["systemd", "dispatcher"]),
+                Arg::new("root")
+                    .help("Install below this directory instead of /")
+                    .long("root")
+                    .value_parser(value_parser!(PathBuf)),
+            ]),
         ])
 }
 
+/// Parses durations such as `90s`, `45m`, `2h` or `1h30m`.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+
+    for c in input.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(format!("Invalid duration unit '{}'", c)),
+        };
+        let value: u64 = number
+            .parse()
+            .map_err(|_| format!("Invalid duration: {}", input))?;
+        seconds = value
+            .checked_mul(unit)
+            .and_then(|value| seconds.checked_add(value))
+            .ok_or_else(|| format!("Duration too long: {}", input))?;
+        number.clear();
+    }
+
+    if !number.is_empty() || seconds == 0 {
+        return Err(format!("Invalid duration: {}", input));
+    }
+
+    Ok(Duration::from_secs(seconds))
+}
+
 pub fn prompt_new_config(name: Option<String>) -> ProxyProfile {
     let proxy_host: String = Input::with_theme(&ColorfulTheme::default())
         .with_prompt("Proxy host")
@@ -175,6 +279,12 @@ pub fn prompt_new_config(name: Option<String>) -> ProxyProfile {
         .map(|x| x.trim().to_string())
         .collect::<Vec<String>>();
 
+    let pac_url: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("PAC URL for the networks' own proxy setting (optional)")
+        .allow_empty(true)
+        .interact_text()
+        .unwrap();
+
     let network_list = get_saved_wifi_networks().unwrap();
 
     println!("List: {}", network_list[0]);
@@ -190,10 +300,9 @@ pub fn prompt_new_config(name: Option<String>) -> ProxyProfile {
         .map(|x| network_list[*x].clone())
         .collect::<Vec<String>>();
 
-    let mut profile_name: String = String::new();
-
-    if name.is_none() {
-        profile_name = Input::with_theme(&ColorfulTheme::default())
+    let profile_name = match name {
+        Some(name) => name,
+        None => Input::with_theme(&ColorfulTheme::default())
             .with_prompt("Profile name")
             .validate_with(|input: &String| -> Result<(), &str> {
                 if input.is_empty() {
@@ -202,22 +311,19 @@ pub fn prompt_new_config(name: Option<String>) -> ProxyProfile {
                 Ok(())
             })
             .interact_text()
-            .unwrap();
-    } else {
-        profile_name = name.unwrap();
-    }
+            .unwrap(),
+    };
 
-    let proxy_settings = ProxySettings::new(
+    let mut proxy_settings = ProxySettings::new(
         proxy_host,
         proxy_port,
         auth_info,
         selected_protocols,
         no_proxy_list,
     );
+    proxy_settings.pac_url = Some(pac_url).filter(|url| !url.is_empty());
 
-    let profile = ProxyProfile::new(profile_name, proxy_settings, selected_networks);
-
-    profile
+    ProxyProfile::new(profile_name, proxy_settings, selected_networks)
 }
 
 fn prompt_auth() -> ProxyAuth {
@@ -245,3 +351,20 @@ fn prompt_auth() -> ProxyAuth {
 
     ProxyAuth::new(username, password)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert!(parse_duration("2").is_err());
+        assert!(parse_duration("2w").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("99999999999999999h").is_err());
+        assert!(parse_duration("18446744073709551615s1s").is_err());
+    }
+}
@@ -1,7 +1,109 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
 pub struct AutoProxyConfig {
     pub shell: String,
     pub desktop_environment: String,
     pub distro: String,
     pub package_manager: String,
     pub hide_socks: bool,
+    /// Show a desktop notification when the daemon switches profiles.
+    pub notifications: bool,
+    pub cargo: CargoConfig,
+    /// Projects whose own `gradle.properties` is managed as well.
+    pub gradle_projects: Vec<PathBuf>,
+    pub java: JavaConfig,
+    /// Install a sudoers rule keeping the proxy variables under `sudo`.
+    pub sudo_env_keep: bool,
+    /// Set Firefox's proxy through the system-wide `policies.json` rather
+    /// than each profile's `user.js`.
+    pub firefox_policies: bool,
+    pub ssh: SshConfig,
+}
+
+/// Extra `[http]` settings for Cargo, useful behind TLS-intercepting proxies.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CargoConfig {
+    /// Sets `http.check-revoke`, usually to false when the proxy's
+    /// certificates cannot be checked for revocation.
+    pub check_revoke: Option<bool>,
+    /// Sets `http.cainfo` to the proxy's CA bundle.
+    pub cainfo: Option<String>,
+}
+
+/// Hosts whose SSH connections are tunnelled through the proxy.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SshConfig {
+    /// `Host` patterns of `~/.ssh/config`; the target is off until some are
+    /// listed.
+    pub hosts: Vec<String>,
+    /// Use OpenBSD netcat as `ProxyCommand` instead of `auto-proxy connect`.
+    pub netcat: bool,
+}
+
+/// How the `java` target passes the proxy to the JVM.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct JavaConfig {
+    /// Also writes `conf/net.properties` of this JDK.
+    pub java_home: Option<PathBuf>,
+    /// Sets `java.net.useSystemProxies` instead of explicit proxy properties,
+    /// leaving the JVM to read the desktop's proxy settings.
+    pub use_system_proxies: bool,
+}
+
+impl Default for AutoProxyConfig {
+    fn default() -> Self {
+        let shell = env::var("SHELL")
+            .ok()
+            .and_then(|shell| shell.rsplit('/').next().map(String::from))
+            .unwrap_or_default();
+        let desktop_environment = env::var("XDG_CURRENT_DESKTOP")
+            .unwrap_or_default()
+            .to_lowercase();
+
+        Self {
+            shell,
+            desktop_environment,
+            distro: String::new(),
+            package_manager: String::new(),
+            hide_socks: false,
+            notifications: false,
+            cargo: CargoConfig::default(),
+            gradle_projects: Vec::new(),
+            java: JavaConfig::default(),
+            sudo_env_keep: false,
+            firefox_policies: false,
+            ssh: SshConfig::default(),
+        }
+    }
+}
+
+impl AutoProxyConfig {
+    pub fn config_file() -> PathBuf {
+        dirs::home_dir()
+            .unwrap()
+            .join(".auto-proxy/config/config.yaml")
+    }
+
+    /// Loads the configuration written by `setup`, falling back to the
+    /// detected defaults when no configuration file exists yet.
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let config_file = Self::config_file();
+        if !config_file.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(config_file)?;
+        let config = serde_yaml::from_str(&content)?;
+        Ok(config)
+    }
 }
@@ -0,0 +1,246 @@
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::thread;
+
+use crate::{proxy::ProxySettings, proxy_profile::ProxyProfile, state::State};
+
+/// The proxy of the active profile, or `None` when it is direct.
+fn active_proxy() -> Result<Option<ProxySettings>, Box<dyn Error>> {
+    let profile = match State::load()?.active_profile {
+        Some(name) => ProxyProfile::get_profile(&name)?,
+        None => return Ok(None),
+    };
+    Ok(Some(profile.proxy_settings).filter(|setting| !setting.host.is_empty()))
+}
+
+/// Reads the response head of a `CONNECT` byte by byte, so that nothing the
+/// tunnelled server sends afterwards is lost.
+fn read_head(stream: &mut impl Read) -> Result<String, Box<dyn Error>> {
+    let mut head = Vec::new();
+    let mut byte = [0; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if stream.read(&mut byte)? == 0 {
+            return Err("Proxy closed the connection".into());
+        }
+        head.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+fn http_connect(
+    stream: &mut (impl Read + Write),
+    setting: &ProxySettings,
+    host: &str,
+    port: u16,
+) -> Result<(), Box<dyn Error>> {
+    let mut request = format!(
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+        host = host,
+        port = port
+    );
+    if let Some(auth) = setting
+        .auth
+        .as_ref()
+        .filter(|auth| !auth.username.is_empty())
+    {
+        request.push_str(&format!("Proxy-Authorization: {}\r\n", auth.basic()));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    let head = read_head(stream)?;
+    let status = head.lines().next().unwrap_or_default();
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(format!("Proxy refused the tunnel: {}", status).into()),
+    }
+}
+
+/// The length prefix of a SOCKS5 field, which holds at most 255 bytes.
+fn field_len(value: &str, field: &str) -> Result<u8, Box<dyn Error>> {
+    u8::try_from(value.len())
+        .map_err(|_| format!("{} is too long for SOCKS5 (over 255 bytes)", field).into())
+}
+
+fn socks5_connect(
+    stream: &mut (impl Read + Write),
+    setting: &ProxySettings,
+    host: &str,
+    port: u16,
+) -> Result<(), Box<dyn Error>> {
+    let auth = setting
+        .auth
+        .as_ref()
+        .filter(|auth| !auth.username.is_empty());
+    let host_len = field_len(host, "Host name")?;
+    let login = match auth {
+        Some(auth) => {
+            let mut login = vec![1, field_len(&auth.username, "Proxy username")?];
+            login.extend_from_slice(auth.username.as_bytes());
+            login.push(field_len(&auth.password, "Proxy password")?);
+            login.extend_from_slice(auth.password.as_bytes());
+            login
+        }
+        None => Vec::new(),
+    };
+    let methods: &[u8] = if auth.is_some() { &[0, 2] } else { &[0] };
+    stream.write_all(&[&[5, methods.len() as u8], methods].concat())?;
+
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply)?;
+    match (reply[1], auth) {
+        (0, _) => {}
+        (2, Some(_)) => {
+            stream.write_all(&login)?;
+            stream.read_exact(&mut reply)?;
+            if reply[1] != 0 {
+                return Err("SOCKS proxy rejected the credentials".into());
+            }
+        }
+        _ => return Err("SOCKS proxy offers no usable authentication".into()),
+    }
+
+    let mut request = vec![5, 1, 0, 3, host_len];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut head = [0; 4];
+    stream.read_exact(&mut head)?;
+    if head[1] != 0 {
+        return Err(format!("SOCKS proxy refused the connection (code {})", head[1]).into());
+    }
+    let address_len = match head[3] {
+        1 => 4,
+        4 => 16,
+        3 => {
+            let mut len = [0; 1];
+            stream.read_exact(&mut len)?;
+            len[0] as usize
+        }
+        _ => return Err("Malformed reply from SOCKS proxy".into()),
+    };
+    let mut bound = vec![0; address_len + 2];
+    stream.read_exact(&mut bound)?;
+    Ok(())
+}
+
+/// Opens a connection to `host:port`, through `setting` if given.
+fn open(
+    setting: Option<&ProxySettings>,
+    host: &str,
+    port: u16,
+) -> Result<TcpStream, Box<dyn Error>> {
+    let setting = match setting {
+        Some(setting) => setting,
+        None => return Ok(TcpStream::connect((host, port))?),
+    };
+
+    let mut stream = TcpStream::connect(format!("{}:{}", setting.host, setting.port))?;
+    let socks_only = setting.socks_only();
+    if socks_only {
+        socks5_connect(&mut stream, setting, host, port)?;
+    } else {
+        http_connect(&mut stream, setting, host, port)?;
+    }
+    Ok(stream)
+}
+
+/// Connects stdin and stdout to `host:port`, as SSH expects of a
+/// `ProxyCommand`.
+pub fn run(host: &str, port: u16) -> Result<(), Box<dyn Error>> {
+    let proxy = active_proxy()?;
+    let stream = open(proxy.as_ref(), host, port)?;
+
+    let mut upstream = stream.try_clone()?;
+    thread::spawn(move || {
+        let _ = io::copy(&mut io::stdin().lock(), &mut upstream);
+        let _ = upstream.shutdown(Shutdown::Write);
+    });
+    io::copy(&mut &stream, &mut io::stdout().lock())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::{ProxyAuth, ProxyProtocol};
+    use std::net::TcpListener;
+
+    fn proxy(listener: &TcpListener, protocols: Vec<ProxyProtocol>) -> ProxySettings {
+        ProxySettings {
+            host: "127.0.0.1".to_string(),
+            port: listener.local_addr().unwrap().port().to_string(),
+            auth: Some(ProxyAuth::new("alice".to_string(), "s3cret".to_string())),
+            protocols,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_http_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let setting = proxy(&listener, vec![ProxyProtocol::Http]);
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_head(&mut stream).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\nSSH-2.0-OpenSSH\r\n")
+                .unwrap();
+            request
+        });
+
+        let mut stream = open(Some(&setting), "github.com", 22).unwrap();
+        let mut banner = String::new();
+        stream.read_to_string(&mut banner).unwrap();
+        assert_eq!(banner, "SSH-2.0-OpenSSH\r\n");
+        assert_eq!(
+            server.join().unwrap(),
+            "CONNECT github.com:22 HTTP/1.1\r\nHost: github.com:22\r\n\
+             Proxy-Authorization: Basic YWxpY2U6czNjcmV0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_socks5_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let setting = proxy(&listener, vec![ProxyProtocol::Socks]);
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut greeting = [0; 4];
+            stream.read_exact(&mut greeting).unwrap();
+            stream.write_all(&[5, 2]).unwrap();
+            let mut login = [0; 14];
+            stream.read_exact(&mut login).unwrap();
+            stream.write_all(&[1, 0]).unwrap();
+            let mut request = [0; 17];
+            stream.read_exact(&mut request).unwrap();
+            stream
+                .write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 22])
+                .unwrap();
+            (greeting, login, request)
+        });
+
+        open(Some(&setting), "github.com", 22).unwrap();
+        let (greeting, login, request) = server.join().unwrap();
+        assert_eq!(greeting, [5, 2, 0, 2]);
+        assert_eq!(&login, b"\x01\x05alice\x06s3cret");
+        assert_eq!(&request, b"\x05\x01\x00\x03\x0agithub.com\x00\x16");
+    }
+
+    #[test]
+    fn test_socks5_field_too_long() {
+        let setting = ProxySettings {
+            auth: Some(ProxyAuth::new("alice".to_string(), "x".repeat(256))),
+            protocols: vec![ProxyProtocol::Socks],
+            ..Default::default()
+        };
+        let mut stream = io::Cursor::new(Vec::new());
+        assert!(socks5_connect(&mut stream, &setting, "github.com", 22).is_err());
+        assert!(
+            socks5_connect(&mut stream, &ProxySettings::default(), &"a".repeat(256), 22).is_err()
+        );
+        assert!(stream.get_ref().is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+use std::env;
+use std::error::Error;
+use std::ffi::CString;
+use std::fs;
+use std::io::{ErrorKind, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use dbus::strings::ErrorName;
+use dbus::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{unix_now, Pin};
+
+pub const DBUS_NAME: &str = "org.autoproxy.Daemon";
+pub const DBUS_PATH: &str = "/org/autoproxy/Daemon";
+pub const DBUS_INTERFACE: &str = "org.autoproxy.Daemon";
+
+pub const DBUS_INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.autoproxy.Daemon">
+    <method name="Status">
+      <arg name="active_profile" type="s" direction="out"/>
+      <arg name="paused" type="b" direction="out"/>
+      <arg name="network" type="s" direction="out"/>
+      <arg name="pin" type="s" direction="out"/>
+    </method>
+    <method name="ListProfiles">
+      <arg name="profiles" type="as" direction="out"/>
+    </method>
+    <method name="Activate">
+      <arg name="profile" type="s" direction="in"/>
+    </method>
+    <method name="Pause"/>
+    <method name="Resume"/>
+    <method name="Pin">
+      <arg name="profile" type="s" direction="in"/>
+      <arg name="seconds" type="t" direction="in"/>
+      <arg name="until_network_change" type="b" direction="in"/>
+    </method>
+    <method name="Unpin"/>
+    <signal name="ProfileChanged">
+      <arg name="profile" type="s"/>
+    </signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect">
+      <arg name="xml_data" type="s" direction="out"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+/// A request to the running daemon, sent as a single line over the control
+/// socket or as a method call on the D-Bus interface.
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    Status,
+    ListProfiles,
+    Activate(String),
+    Pause,
+    Resume,
+    Pin {
+        profile: String,
+        duration: Option<Duration>,
+        until_network_change: bool,
+    },
+    Unpin,
+}
+
+impl Request {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (command, argument) = match line.split_once(' ') {
+            Some((command, argument)) => (command, Some(argument.trim())),
+            None => (line, None),
+        };
+
+        match (command, argument) {
+            ("status", None) => Ok(Request::Status),
+            ("list-profiles", None) => Ok(Request::ListProfiles),
+            ("activate", Some(profile)) => Ok(Request::Activate(profile.to_string())),
+            ("pause", None) => Ok(Request::Pause),
+            ("resume", None) => Ok(Request::Resume),
+            ("pin", Some(argument)) => {
+                Self::parse_pin(argument).ok_or(format!("Invalid pin: {}", argument))
+            }
+            ("unpin", None) => Ok(Request::Unpin),
+            _ => Err(format!("Invalid request: {}", line)),
+        }
+    }
+
+    /// Parses `<seconds> <until-network-change> <profile>`, where zero
+    /// seconds means the pin does not expire on its own.
+    fn parse_pin(argument: &str) -> Option<Self> {
+        let mut parts = argument.splitn(3, ' ');
+        let seconds: u64 = parts.next()?.parse().ok()?;
+        let until_network_change: bool = parts.next()?.parse().ok()?;
+        let profile = parts.next()?.trim();
+        if profile.is_empty() {
+            return None;
+        }
+
+        Some(Request::Pin {
+            profile: profile.to_string(),
+            duration: (seconds > 0).then(|| Duration::from_secs(seconds)),
+            until_network_change,
+        })
+    }
+
+    pub fn to_line(&self) -> String {
+        match self {
+            Request::Status => "status".to_string(),
+            Request::ListProfiles => "list-profiles".to_string(),
+            Request::Activate(profile) => format!("activate {}", profile),
+            Request::Pause => "pause".to_string(),
+            Request::Resume => "resume".to_string(),
+            Request::Pin {
+                profile,
+                duration,
+                until_network_change,
+            } => format!(
+                "pin {} {} {}",
+                duration.map_or(0, |duration| duration.as_secs()),
+                until_network_change,
+                profile
+            ),
+            Request::Unpin => "unpin".to_string(),
+        }
+    }
+
+    pub fn from_dbus(msg: &Message) -> Result<Self, String> {
+        match msg.member().as_deref() {
+            Some("Status") => Ok(Request::Status),
+            Some("ListProfiles") => Ok(Request::ListProfiles),
+            Some("Activate") => msg
+                .read1::<&str>()
+                .map(|profile| Request::Activate(profile.to_string()))
+                .map_err(|err| err.to_string()),
+            Some("Pause") => Ok(Request::Pause),
+            Some("Resume") => Ok(Request::Resume),
+            Some("Pin") => {
+                let (profile, seconds, until_network_change): (&str, u64, bool) =
+                    msg.read3().map_err(|err| err.to_string())?;
+                Ok(Request::Pin {
+                    profile: profile.to_string(),
+                    duration: (seconds > 0).then(|| Duration::from_secs(seconds)),
+                    until_network_change,
+                })
+            }
+            Some("Unpin") => Ok(Request::Unpin),
+            member => Err(format!("Unknown method: {}", member.unwrap_or_default())),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Status {
+    pub active_profile: Option<String>,
+    pub paused: bool,
+    pub network: Option<String>,
+    pub pin: Option<Pin>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub enum Response {
+    Status(Status),
+    Profiles(Vec<String>),
+    Done,
+    Error(String),
+}
+
+impl Response {
+    pub fn into_dbus(self, msg: &Message) -> Message {
+        match self {
+            Response::Status(status) => msg
+                .method_return()
+                .append3(
+                    status.active_profile.unwrap_or_default(),
+                    status.paused,
+                    status.network.unwrap_or_default(),
+                )
+                .append1(
+                    status
+                        .pin
+                        .map(|pin| pin.describe(unix_now()))
+                        .unwrap_or_default(),
+                ),
+            Response::Profiles(profiles) => msg.method_return().append1(profiles),
+            Response::Done => msg.method_return(),
+            Response::Error(err) => msg.error(
+                &ErrorName::from("org.autoproxy.Daemon.Error"),
+                &CString::new(err.replace('\0', "")).unwrap(),
+            ),
+        }
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) => PathBuf::from(runtime_dir).join("auto-proxy.sock"),
+        None => dirs::home_dir().unwrap().join(".auto-proxy/daemon.sock"),
+    }
+}
+
+/// Sends `request` to the daemon listening on `path` and waits for its reply.
+pub fn send_request(path: &Path, request: &Request) -> Result<Response, Box<dyn Error>> {
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+    writeln!(stream, "{}", request.to_line())?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut content = String::new();
+    stream.read_to_string(&mut content)?;
+    Ok(serde_yaml::from_str(&content)?)
+}
+
+/// Non-blocking listener for the control socket, polled from the daemon loop.
+pub struct ControlSocket {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl ControlSocket {
+    pub fn bind(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(format!("A daemon is already listening on {}", path.display()).into());
+            }
+            fs::remove_file(path)?;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            listener,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Answers every client currently waiting to be accepted.
+    pub fn serve_pending(
+        &self,
+        handler: &mut dyn FnMut(Request) -> Response,
+    ) -> Result<(), Box<dyn Error>> {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(err) = Self::serve(stream, handler) {
+                        eprintln!("Control socket client failed: {}", err);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// How long a client may take to send its request and read the reply,
+    /// since the daemon loop waits on it.
+    const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Reads one line within `CLIENT_TIMEOUT` overall, so that a client
+    /// trickling bytes cannot hold the daemon loop either.
+    fn read_request_line(stream: &UnixStream) -> Result<String, Box<dyn Error>> {
+        let deadline = Instant::now() + Self::CLIENT_TIMEOUT;
+        let mut line = Vec::new();
+        let mut buffer = [0; 512];
+        while !line.contains(&b'\n') {
+            let remaining = deadline
+                .checked_duration_since(Instant::now())
+                .filter(|remaining| !remaining.is_zero())
+                .ok_or("Timed out waiting for the request")?;
+            stream.set_read_timeout(Some(remaining))?;
+            let read = (&*stream).read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            line.extend_from_slice(&buffer[..read]);
+        }
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    }
+
+    fn serve(
+        mut stream: UnixStream,
+        handler: &mut dyn FnMut(Request) -> Response,
+    ) -> Result<(), Box<dyn Error>> {
+        stream.set_nonblocking(false)?;
+        stream.set_write_timeout(Some(Self::CLIENT_TIMEOUT))?;
+        let line = Self::read_request_line(&stream)?;
+
+        let response = match Request::parse(&line) {
+            Ok(request) => handler(request),
+            Err(err) => Response::Error(err),
+        };
+
+        stream.write_all(serde_yaml::to_string(&response)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_request_lines() {
+        for request in [
+            Request::Status,
+            Request::ListProfiles,
+            Request::Activate("office vpn".to_string()),
+            Request::Pause,
+            Request::Resume,
+            Request::Pin {
+                profile: "direct".to_string(),
+                duration: Some(Duration::from_secs(7200)),
+                until_network_change: false,
+            },
+            Request::Pin {
+                profile: "office vpn".to_string(),
+                duration: None,
+                until_network_change: true,
+            },
+            Request::Unpin,
+        ] {
+            assert_eq!(Request::parse(&request.to_line()).unwrap(), request);
+        }
+        assert!(Request::parse("activate").is_err());
+        assert!(Request::parse("status now").is_err());
+        assert!(Request::parse("pin 2h false direct").is_err());
+    }
+
+    #[test]
+    fn test_control_socket() {
+        let path = env::temp_dir().join(format!("auto-proxy-control-{}.sock", std::process::id()));
+        let socket = ControlSocket::bind(&path).unwrap();
+
+        let client_path = path.clone();
+        let client = thread::spawn(move || {
+            send_request(&client_path, &Request::Activate("office".to_string())).unwrap()
+        });
+
+        let mut activated = None;
+        while !client.is_finished() {
+            socket
+                .serve_pending(&mut |request| {
+                    activated = Some(request);
+                    Response::Done
+                })
+                .unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(client.join().unwrap(), Response::Done);
+        assert_eq!(activated, Some(Request::Activate("office".to_string())));
+
+        // A client that never writes only holds the loop for the timeout.
+        let _silent = UnixStream::connect(&path).unwrap();
+        let started = Instant::now();
+        socket.serve_pending(&mut |_| Response::Done).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        drop(socket);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,567 @@
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use dbus::blocking::stdintf::org_freedesktop_dbus::RequestNameReply;
+use dbus::blocking::LocalConnection;
+use dbus::channel::{MatchingReceiver, Sender};
+use dbus::message::MatchRule;
+use dbus::Message;
+
+use crate::{
+    config::AutoProxyConfig,
+    control::{
+        send_request, socket_path, ControlSocket, Request, Response, Status, DBUS_INTERFACE,
+        DBUS_INTROSPECTION, DBUS_NAME, DBUS_PATH,
+    },
+    network::get_current_wifi_network,
+    notify::{Notification, ACTION_PIN, ACTION_UNDO, NOTIFICATIONS_INTERFACE},
+    proxy_profile::ProxyProfile,
+    state::{unix_now, Pin, State},
+    target::{apply_profile, enabled_targets, ApplyReport, ProxyTarget},
+};
+
+const NM_INTERFACE: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+
+/// Built-in profile name that unsets the proxy on every target.
+pub const DIRECT_PROFILE: &str = "direct";
+
+/// Picks the first profile that lists `network` in its `auto_apply_networks`.
+pub fn select_profile<'a>(
+    profiles: &'a [ProxyProfile],
+    network: Option<&str>,
+) -> Option<&'a ProxyProfile> {
+    let network = network?;
+    profiles.iter().find(|profile| {
+        profile
+            .auto_apply_networks
+            .iter()
+            .any(|auto_apply_network| auto_apply_network == network)
+    })
+}
+
+/// Delays evaluation until network events have stopped arriving for `delay`,
+/// so that a flapping Wi-Fi connection only triggers a single evaluation.
+pub struct Debouncer {
+    delay: Duration,
+    pending_since: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending_since: None,
+        }
+    }
+
+    pub fn trigger(&mut self, now: Instant) {
+        self.pending_since = Some(now);
+    }
+
+    pub fn ready(&mut self, now: Instant) -> bool {
+        match self.pending_since {
+            Some(since) if now.duration_since(since) >= self.delay => {
+                self.pending_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The last switch notification, kept to answer its Undo and Pin actions.
+struct SentNotification {
+    id: u32,
+    previous_profile: Option<String>,
+    applied_profile: Option<String>,
+}
+
+pub struct Daemon {
+    targets: Vec<Box<dyn ProxyTarget>>,
+    state: State,
+    notifications: bool,
+    notification: Option<SentNotification>,
+}
+
+impl Daemon {
+    /// `gradle_projects` are managed in addition to those in the
+    /// configuration. They are remembered in the state, so that requests
+    /// handled in-process while the daemon is down apply to the same
+    /// projects; `None` takes the remembered ones.
+    pub fn new(
+        mut config: AutoProxyConfig,
+        gradle_projects: Option<Vec<PathBuf>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut state = State::load()?;
+        if let Some(gradle_projects) = gradle_projects {
+            state.gradle_projects = gradle_projects;
+        }
+        config
+            .gradle_projects
+            .extend(state.gradle_projects.iter().cloned());
+
+        Ok(Self {
+            targets: enabled_targets(&config),
+            state,
+            notifications: config.notifications,
+            notification: None,
+        })
+    }
+
+    fn apply(&mut self, selected: Option<&ProxyProfile>) -> Result<ApplyReport, Box<dyn Error>> {
+        let report = apply_profile(&self.targets, selected);
+        self.state.active_profile = selected.map(|profile| profile.name.clone());
+        self.state.save()?;
+
+        Ok(report)
+    }
+
+    fn pin_expired(&self, now: u64) -> bool {
+        self.state
+            .pin
+            .as_ref()
+            .is_some_and(|pin| pin.until.is_some_and(|until| now >= until))
+    }
+
+    fn clear_pin(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(pin) = self.state.pin.take() {
+            println!("Unpinned profile: {}", pin.profile);
+            self.state.save()?;
+        }
+        Ok(())
+    }
+
+    /// Selects the profile for the current network and applies it through
+    /// the targets, unless auto-apply is paused, a profile is pinned or the
+    /// selection is already active.
+    pub fn evaluate(&mut self) -> Result<Option<ApplyReport>, Box<dyn Error>> {
+        if self.state.paused {
+            return Ok(None);
+        }
+
+        let now = unix_now();
+        if self.pin_expired(now) {
+            self.clear_pin()?;
+        }
+
+        let network = get_current_wifi_network()?;
+        if let Some(pin) = &self.state.pin {
+            if pin.is_active(now, network.as_deref()) {
+                return Ok(None);
+            }
+            self.clear_pin()?;
+        }
+
+        let profiles = ProxyProfile::load_all()?;
+        let selected = select_profile(&profiles, network.as_deref());
+
+        if selected.map(|profile| &profile.name) == self.state.active_profile.as_ref() {
+            return Ok(None);
+        }
+
+        self.apply(selected).map(Some)
+    }
+
+    pub fn activate(&mut self, profile_name: &str) -> Result<ApplyReport, Box<dyn Error>> {
+        match ProxyProfile::get_profile(profile_name) {
+            Ok(profile) => self.apply(Some(&profile)),
+            Err(_) if profile_name == DIRECT_PROFILE => self.apply(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Activates `profile_name` and suspends automatic selection until the
+    /// pin expires or is removed.
+    pub fn pin(
+        &mut self,
+        profile_name: &str,
+        duration: Option<Duration>,
+        until_network_change: bool,
+    ) -> Result<ApplyReport, Box<dyn Error>> {
+        let network = get_current_wifi_network().ok().flatten();
+        let report = self.activate(profile_name)?;
+
+        self.state.pin = Some(Pin {
+            profile: profile_name.to_string(),
+            until: duration.map(|duration| unix_now().saturating_add(duration.as_secs())),
+            until_network_change,
+            network,
+        });
+        self.state.save()?;
+
+        Ok(report)
+    }
+
+    pub fn status(&self) -> Status {
+        Status {
+            active_profile: self.state.active_profile.clone(),
+            paused: self.state.paused,
+            network: get_current_wifi_network().ok().flatten(),
+            pin: self.state.pin.clone(),
+        }
+    }
+
+    fn notify_switch(
+        &mut self,
+        session: &LocalConnection,
+        previous_profile: Option<String>,
+        report: &ApplyReport,
+    ) {
+        let applied_profile = self.state.active_profile.clone();
+        let notification = Notification::profile_switched(applied_profile.as_deref(), report);
+
+        match notification.send(session) {
+            Ok(id) => {
+                self.notification = Some(SentNotification {
+                    id,
+                    previous_profile,
+                    applied_profile,
+                })
+            }
+            Err(err) => eprintln!("Failed to send notification: {}", err),
+        }
+    }
+
+    /// Undo pins the profile that was active before the switch, Pin keeps
+    /// the new one; either way until the network changes again.
+    fn notification_action(&mut self, id: u32, action: &str) {
+        let sent = match self.notification.take() {
+            Some(sent) if sent.id == id => sent,
+            other => {
+                self.notification = other;
+                return;
+            }
+        };
+
+        let profile = match action {
+            ACTION_UNDO => sent.previous_profile,
+            ACTION_PIN => sent.applied_profile,
+            _ => return,
+        }
+        .unwrap_or_else(|| DIRECT_PROFILE.to_string());
+
+        match self.pin(&profile, None, true) {
+            Ok(report) => print_report(Some(&profile), &report),
+            Err(err) => eprintln!("Failed to pin {}: {}", profile, err),
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) -> Response {
+        self.state.paused = paused;
+        match self.state.save() {
+            Ok(_) => Response::Done,
+            Err(err) => Response::Error(err.to_string()),
+        }
+    }
+
+    pub fn handle(&mut self, request: Request) -> Response {
+        match request {
+            Request::Status => Response::Status(self.status()),
+            Request::ListProfiles => match ProxyProfile::load_all() {
+                Ok(profiles) => {
+                    Response::Profiles(profiles.into_iter().map(|profile| profile.name).collect())
+                }
+                Err(err) => Response::Error(err.to_string()),
+            },
+            Request::Activate(profile_name) => match self.activate(&profile_name) {
+                Ok(report) => {
+                    print_report(Some(&profile_name), &report);
+                    Response::Done
+                }
+                Err(err) => Response::Error(err.to_string()),
+            },
+            Request::Pause => self.set_paused(true),
+            Request::Resume => {
+                let response = self.set_paused(false);
+                evaluate_and_report(self);
+                response
+            }
+            Request::Pin {
+                profile,
+                duration,
+                until_network_change,
+            } => match self.pin(&profile, duration, until_network_change) {
+                Ok(report) => {
+                    print_report(Some(&profile), &report);
+                    Response::Done
+                }
+                Err(err) => Response::Error(err.to_string()),
+            },
+            Request::Unpin => match self.clear_pin() {
+                Ok(_) => {
+                    evaluate_and_report(self);
+                    Response::Done
+                }
+                Err(err) => Response::Error(err.to_string()),
+            },
+        }
+    }
+}
+
+fn print_report(profile: Option<&str>, report: &ApplyReport) {
+    println!("Applied profile: {}", profile.unwrap_or("direct"));
+    for target in &report.succeeded {
+        println!("  {}: ok", target);
+    }
+    for (target, err) in &report.failed {
+        println!("  {}: {}", target, err);
+    }
+    for (target, follow_up) in &report.follow_ups {
+        println!("  {}: {}", target, follow_up);
+    }
+}
+
+fn evaluate_and_report(daemon: &mut Daemon) -> Option<ApplyReport> {
+    match daemon.evaluate() {
+        Ok(Some(report)) => {
+            print_report(daemon.state.active_profile.as_deref(), &report);
+            Some(report)
+        }
+        Ok(None) => None,
+        Err(err) => {
+            eprintln!("Failed to apply proxy: {}", err);
+            None
+        }
+    }
+}
+
+/// Exposes `org.autoproxy.Daemon` on the session bus, answering method calls
+/// with the same handler as the control socket.
+fn serve_dbus(daemon: Rc<RefCell<Daemon>>) -> Result<LocalConnection, Box<dyn Error>> {
+    let session = LocalConnection::new_session()?;
+    if session.request_name(DBUS_NAME, false, true, true)? != RequestNameReply::PrimaryOwner {
+        return Err(format!("{} is already owned on the session bus", DBUS_NAME).into());
+    }
+
+    session.start_receive(
+        MatchRule::new_method_call()
+            .with_path(DBUS_PATH)
+            .with_interface(DBUS_INTERFACE),
+        Box::new(move |msg, conn| {
+            let response = match Request::from_dbus(&msg) {
+                Ok(request) => daemon.borrow_mut().handle(request),
+                Err(err) => Response::Error(err),
+            };
+            let _ = conn.send(response.into_dbus(&msg));
+            true
+        }),
+    );
+    session.start_receive(
+        MatchRule::new_method_call()
+            .with_path(DBUS_PATH)
+            .with_interface("org.freedesktop.DBus.Introspectable")
+            .with_member("Introspect"),
+        Box::new(|msg, conn| {
+            let _ = conn.send(msg.method_return().append1(DBUS_INTROSPECTION));
+            true
+        }),
+    );
+
+    Ok(session)
+}
+
+fn watch_notification_actions(
+    session: &LocalConnection,
+    daemon: Rc<RefCell<Daemon>>,
+) -> Result<(), Box<dyn Error>> {
+    session.add_match(
+        MatchRule::new_signal(NOTIFICATIONS_INTERFACE, "ActionInvoked"),
+        move |(id, action): (u32, String), _, _| {
+            daemon.borrow_mut().notification_action(id, &action);
+            true
+        },
+    )?;
+
+    Ok(())
+}
+
+fn emit_profile_changed(session: &LocalConnection, profile: Option<&str>) {
+    if let Ok(signal) = Message::new_signal(DBUS_PATH, DBUS_INTERFACE, "ProfileChanged") {
+        let _ = session.send(signal.append1(profile.unwrap_or_default()));
+    }
+}
+
+/// Evaluates once when `once` is set, otherwise listens for NetworkManager
+/// signals and re-evaluates after `debounce` of quiet, while serving the
+/// control socket and D-Bus interface. `gradle_projects` are managed in
+/// addition to those in the configuration.
+pub fn run(
+    once: bool,
+    debounce: Duration,
+    gradle_projects: Vec<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let daemon = Daemon::new(AutoProxyConfig::load()?, Some(gradle_projects))?;
+    daemon.state.save()?;
+    let daemon = Rc::new(RefCell::new(daemon));
+
+    if once {
+        evaluate_and_report(&mut daemon.borrow_mut());
+        return Ok(());
+    }
+
+    let system = LocalConnection::new_system()?;
+    let network_changed = Rc::new(Cell::new(false));
+
+    let rules = [
+        MatchRule::new_signal(NM_INTERFACE, "StateChanged"),
+        MatchRule::new_signal("org.freedesktop.DBus.Properties", "PropertiesChanged")
+            .with_path(NM_PATH),
+    ];
+    for rule in rules {
+        let network_changed = network_changed.clone();
+        system.add_match(rule, move |_: (), _, _| {
+            network_changed.set(true);
+            true
+        })?;
+    }
+
+    let socket = ControlSocket::bind(&socket_path())?;
+    let session = match serve_dbus(daemon.clone()) {
+        Ok(session) => Some(session),
+        Err(err) => {
+            eprintln!("D-Bus control interface unavailable: {}", err);
+            None
+        }
+    };
+
+    if let Some(session) = &session {
+        if daemon.borrow().notifications {
+            watch_notification_actions(session, daemon.clone())?;
+        }
+    }
+
+    let mut debouncer = Debouncer::new(debounce);
+    debouncer.trigger(Instant::now());
+    let mut announced_profile = daemon.borrow().state.active_profile.clone();
+
+    loop {
+        system.process(Duration::from_millis(250))?;
+        if let Some(session) = &session {
+            while session.process(Duration::ZERO)? {}
+        }
+        socket.serve_pending(&mut |request| daemon.borrow_mut().handle(request))?;
+
+        let now = Instant::now();
+        if network_changed.replace(false) {
+            debouncer.trigger(now);
+        }
+        let pin_expired = daemon.borrow().pin_expired(unix_now());
+        if debouncer.ready(now) || pin_expired {
+            let mut daemon = daemon.borrow_mut();
+            let previous_profile = daemon.state.active_profile.clone();
+            if let Some(report) = evaluate_and_report(&mut daemon) {
+                if let (true, Some(session)) = (daemon.notifications, &session) {
+                    daemon.notify_switch(session, previous_profile, &report);
+                }
+            }
+        }
+
+        let active_profile = daemon.borrow().state.active_profile.clone();
+        if active_profile != announced_profile {
+            if let Some(session) = &session {
+                emit_profile_changed(session, active_profile.as_deref());
+            }
+            announced_profile = active_profile;
+        }
+    }
+}
+
+/// Prints the daemon's status, or the last persisted state when it is not
+/// running.
+pub fn print_status() -> Result<(), Box<dyn Error>> {
+    let (running, status) = match send_request(&socket_path(), &Request::Status) {
+        Ok(Response::Status(status)) => (true, status),
+        Ok(Response::Error(err)) => return Err(err.into()),
+        Ok(_) => return Err("Unexpected response from daemon".into()),
+        Err(_) => {
+            let state = State::load()?;
+            let status = Status {
+                active_profile: state.active_profile,
+                paused: state.paused,
+                network: get_current_wifi_network().ok().flatten(),
+                pin: state.pin,
+            };
+            (false, status)
+        }
+    };
+
+    println!(
+        "Daemon: {}",
+        if running { "running" } else { "not running" }
+    );
+    println!(
+        "Active profile: {}",
+        status.active_profile.as_deref().unwrap_or("direct")
+    );
+    println!("Network: {}", status.network.as_deref().unwrap_or("none"));
+    println!(
+        "Auto-apply: {}",
+        if status.paused { "paused" } else { "active" }
+    );
+    if let Some(pin) = status.pin {
+        println!("Pinned: {}", pin.describe(unix_now()));
+    }
+
+    Ok(())
+}
+
+/// Hands `request` to the running daemon, or handles it in-process when no
+/// daemon is listening.
+pub fn submit(request: Request) -> Result<(), Box<dyn Error>> {
+    let response = match send_request(&socket_path(), &request) {
+        Ok(response) => response,
+        Err(_) => Daemon::new(AutoProxyConfig::load()?, None)?.handle(request),
+    };
+
+    match response {
+        Response::Error(err) => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::ProxySettings;
+
+    fn profile(name: &str, networks: &[&str]) -> ProxyProfile {
+        ProxyProfile::new(
+            name.to_string(),
+            ProxySettings::default(),
+            networks.iter().map(|network| network.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let profiles = vec![
+            profile("home", &["HomeWifi"]),
+            profile("office", &["Corp", "Corp-Guest"]),
+        ];
+
+        let selected = select_profile(&profiles, Some("Corp-Guest")).unwrap();
+        assert_eq!(selected.name, "office");
+        assert!(select_profile(&profiles, Some("Cafe")).is_none());
+        assert!(select_profile(&profiles, None).is_none());
+    }
+
+    #[test]
+    fn test_debouncer() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_secs(3));
+        assert!(!debouncer.ready(start));
+
+        debouncer.trigger(start);
+        assert!(!debouncer.ready(start + Duration::from_secs(2)));
+
+        // A second event while waiting restarts the window.
+        debouncer.trigger(start + Duration::from_secs(2));
+        assert!(!debouncer.ready(start + Duration::from_secs(4)));
+        assert!(debouncer.ready(start + Duration::from_secs(5)));
+        assert!(!debouncer.ready(start + Duration::from_secs(6)));
+    }
+}
@@ -1,11 +1,20 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
 use cli_parser::{cli, prompt_new_config};
+use control::Request;
 
 mod cli_parser;
 mod config;
+mod connect;
+mod control;
+mod daemon;
 mod network;
+mod notify;
 mod proxy;
 mod proxy_profile;
 mod setup;
+mod state;
 mod target;
 
 fn main() {
@@ -18,10 +27,9 @@ fn main() {
                 let name = new_matches.get_one::<String>("config-name");
                 let config = prompt_new_config(name.cloned());
 
-                let res = match config.create_profile() {
-                    Ok(_) => (),
-                    Err(err) => panic!("{}", err),
-                };
+                if let Err(err) = config.create_profile() {
+                    panic!("{}", err);
+                }
                 println!("Creating new proxy configuration: ");
             }
             Some(("use", use_matches)) => {
@@ -60,8 +68,67 @@ fn main() {
         Some(("show", _show_matches)) => {
             println!("Showing current proxy");
         }
-        Some(("auto-apply", _auto_apply_matches)) => {
-            println!("Applying proxy automatically based on network");
+        Some(("status", _status_matches)) => {
+            if let Err(err) = daemon::print_status() {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        Some(("connections", _connections_matches)) => {
+            if let Err(err) = network::print_connection_proxies() {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        Some(("connect", connect_matches)) => {
+            let host: &String = connect_matches.get_one("host").unwrap();
+            let port: &u16 = connect_matches.get_one("port").unwrap();
+            if let Err(err) = connect::run(host, *port) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        Some(("pin", pin_matches)) => {
+            let request = Request::Pin {
+                profile: pin_matches.get_one::<String>("profile").unwrap().clone(),
+                duration: pin_matches.get_one::<Duration>("for").copied(),
+                until_network_change: pin_matches.get_flag("until-network-change"),
+            };
+            if let Err(err) = daemon::submit(request) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        Some(("unpin", _unpin_matches)) => {
+            if let Err(err) = daemon::submit(Request::Unpin) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        Some(("auto-apply", auto_apply_matches)) => {
+            let once = auto_apply_matches.get_flag("once");
+            let debounce: &u64 = auto_apply_matches.get_one("debounce").unwrap();
+            let gradle_projects = auto_apply_matches
+                .get_many::<PathBuf>("gradle-project")
+                .unwrap_or_default()
+                .cloned()
+                .collect();
+            if let Err(err) = daemon::run(once, Duration::from_secs(*debounce), gradle_projects) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        Some(("setup", setup_matches)) => {
+            let options = setup::SetupOptions {
+                root: setup_matches.get_one::<PathBuf>("root").cloned(),
+                systemd: setup_matches.get_flag("systemd"),
+                dispatcher: setup_matches.get_flag("dispatcher"),
+                uninstall: setup_matches.get_flag("uninstall"),
+            };
+            if let Err(err) = setup::do_setup(options) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
         }
         _ => unreachable!(),
     }
@@ -1,5 +1,39 @@
+use std::collections::HashMap;
+use std::error::Error;
 use std::process::Command;
 use std::str;
+use std::time::Duration;
+
+use dbus::arg::{prop_cast, PropMap, Variant};
+use dbus::blocking::Connection;
+
+const NM_SERVICE: &str = "org.freedesktop.NetworkManager";
+const NM_SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const NM_SETTINGS_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings";
+const NM_CONNECTION_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+const DBUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// NetworkManager's `proxy.method` values.
+const PROXY_METHOD_NONE: i32 = 0;
+const PROXY_METHOD_AUTO: i32 = 1;
+
+type ConnectionSettings = HashMap<String, PropMap>;
+
+/// The `proxy` setting of a saved NetworkManager connection.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConnectionProxy {
+    /// `proxy.method` is `auto`; without a PAC URL this means WPAD.
+    pub auto: bool,
+    pub pac_url: Option<String>,
+    pub browser_only: bool,
+}
+
+pub struct SavedConnection {
+    pub path: dbus::Path<'static>,
+    pub id: String,
+    pub uuid: String,
+    pub proxy: ConnectionProxy,
+}
 
 pub fn get_saved_wifi_networks() -> Result<Vec<String>, String> {
     let output = Command::new("sudo")
@@ -17,7 +51,7 @@ pub fn get_saved_wifi_networks() -> Result<Vec<String>, String> {
 
     let networks: Vec<String> = output_str
         .lines()
-        .map(|line| line.split(':').nth(0).unwrap_or("").trim().to_string())
+        .map(|line| line.split(':').next().unwrap_or("").trim().to_string())
         .filter(|ssid| !ssid.is_empty())
         .collect();
 
@@ -26,7 +60,7 @@ pub fn get_saved_wifi_networks() -> Result<Vec<String>, String> {
 
 pub fn get_current_wifi_network() -> Result<Option<String>, String> {
     let output = Command::new("nmcli")
-        .args(&[
+        .args([
             "-t",
             "-f",
             "NAME,DEVICE,TYPE",
@@ -56,6 +90,7 @@ pub fn get_current_wifi_network() -> Result<Option<String>, String> {
     Ok(current_network)
 }
 
+#[allow(dead_code)]
 pub fn print_sudoers_setup_instruction() {
     println!("To allow your program to execute nmcli with root permissions, follow these steps:");
     println!("1. Open a terminal and run the following command to edit the sudoers file:");
@@ -68,3 +103,132 @@ pub fn print_sudoers_setup_instruction() {
     println!("Please make sure to replace your_username and /path/to/your/rust/program with the appropriate values.");
     println!("This will allow your program to execute the nmcli command with elevated permissions without prompting for a password.");
 }
+
+fn connection_proxy(settings: &ConnectionSettings) -> ConnectionProxy {
+    let proxy = match settings.get("proxy") {
+        Some(proxy) => proxy,
+        None => return ConnectionProxy::default(),
+    };
+    ConnectionProxy {
+        auto: prop_cast::<i32>(proxy, "method") == Some(&PROXY_METHOD_AUTO),
+        pac_url: prop_cast::<String>(proxy, "pac-url")
+            .filter(|url| !url.is_empty())
+            .cloned(),
+        browser_only: prop_cast::<bool>(proxy, "browser-only")
+            .copied()
+            .unwrap_or_default(),
+    }
+}
+
+fn set_connection_proxy(settings: &mut ConnectionSettings, proxy: &ConnectionProxy) {
+    let section = settings.entry("proxy".to_string()).or_default();
+    let method = if proxy.auto {
+        PROXY_METHOD_AUTO
+    } else {
+        PROXY_METHOD_NONE
+    };
+    section.insert("method".to_string(), Variant(Box::new(method)));
+    section.insert(
+        "browser-only".to_string(),
+        Variant(Box::new(proxy.browser_only)),
+    );
+    match &proxy.pac_url {
+        Some(url) => {
+            section.insert("pac-url".to_string(), Variant(Box::new(url.clone())));
+        }
+        None => {
+            section.remove("pac-url");
+        }
+    }
+}
+
+fn connection_settings(
+    conn: &Connection,
+    path: &dbus::Path<'static>,
+) -> Result<ConnectionSettings, Box<dyn Error>> {
+    let (settings,): (ConnectionSettings,) = conn
+        .with_proxy(NM_SERVICE, path, DBUS_TIMEOUT)
+        .method_call(NM_CONNECTION_INTERFACE, "GetSettings", ())?;
+    Ok(settings)
+}
+
+/// Lists the saved connections through the NetworkManager D-Bus API.
+pub fn saved_connections() -> Result<Vec<SavedConnection>, Box<dyn Error>> {
+    let conn = Connection::new_system()?;
+    let (paths,): (Vec<dbus::Path<'static>>,) = conn
+        .with_proxy(NM_SERVICE, NM_SETTINGS_PATH, DBUS_TIMEOUT)
+        .method_call(NM_SETTINGS_INTERFACE, "ListConnections", ())?;
+
+    let mut connections = Vec::new();
+    for path in paths {
+        let settings = connection_settings(&conn, &path)?;
+        let value = |key: &str| {
+            settings
+                .get("connection")
+                .and_then(|connection| prop_cast::<String>(connection, key))
+                .cloned()
+                .unwrap_or_default()
+        };
+        connections.push(SavedConnection {
+            id: value("id"),
+            uuid: value("uuid"),
+            proxy: connection_proxy(&settings),
+            path,
+        });
+    }
+    Ok(connections)
+}
+
+/// Replaces the `proxy` setting of the connection at `path` with
+/// `Settings.Connection.Update`, which takes the complete settings.
+pub fn update_connection_proxy(
+    path: &dbus::Path<'static>,
+    proxy: &ConnectionProxy,
+) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::new_system()?;
+    let mut settings = connection_settings(&conn, path)?;
+    set_connection_proxy(&mut settings, proxy);
+    conn.with_proxy(NM_SERVICE, path, DBUS_TIMEOUT)
+        .method_call::<(), _, _, _>(NM_CONNECTION_INTERFACE, "Update", (settings,))?;
+    Ok(())
+}
+
+/// Prints each saved connection with the proxy it carries.
+pub fn print_connection_proxies() -> Result<(), Box<dyn Error>> {
+    for connection in saved_connections()? {
+        let proxy = &connection.proxy;
+        let mut description = match (proxy.auto, &proxy.pac_url) {
+            (false, _) => "none".to_string(),
+            (true, Some(url)) => format!("auto, {}", url),
+            (true, None) => "auto, WPAD".to_string(),
+        };
+        if proxy.browser_only {
+            description.push_str(" (browser only)");
+        }
+        println!("{} ({}): {}", connection.id, connection.uuid, description);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_proxy_round_trip() {
+        let mut settings = ConnectionSettings::new();
+        assert_eq!(connection_proxy(&settings), ConnectionProxy::default());
+
+        let proxy = ConnectionProxy {
+            auto: true,
+            pac_url: Some("http://wpad.corp/proxy.pac".to_string()),
+            browser_only: false,
+        };
+        set_connection_proxy(&mut settings, &proxy);
+        assert_eq!(connection_proxy(&settings), proxy);
+
+        set_connection_proxy(&mut settings, &ConnectionProxy::default());
+        assert_eq!(connection_proxy(&settings), ConnectionProxy::default());
+        assert!(!settings["proxy"].contains_key("pac-url"));
+    }
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use dbus::arg::PropMap;
+use dbus::blocking::LocalConnection;
+
+use crate::target::ApplyReport;
+
+pub const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+pub const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+pub const NOTIFICATIONS_INTERFACE: &str = "org.freedesktop.Notifications";
+
+pub const ACTION_UNDO: &str = "undo";
+pub const ACTION_PIN: &str = "pin";
+
+#[derive(Debug, PartialEq)]
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+    /// Pairs of action key and button label.
+    pub actions: Vec<(&'static str, &'static str)>,
+}
+
+impl Notification {
+    /// Describes an automatic switch to `profile`, listing the targets that
+    /// succeeded and failed.
+    pub fn profile_switched(profile: Option<&str>, report: &ApplyReport) -> Self {
+        let mut body = String::new();
+        if !report.succeeded.is_empty() {
+            body.push_str(&format!("Applied to: {}\n", report.succeeded.join(", ")));
+        }
+        if !report.failed.is_empty() {
+            let failed = report
+                .failed
+                .iter()
+                .map(|(target, err)| format!("{} ({})", target, err))
+                .collect::<Vec<String>>();
+            body.push_str(&format!("Failed: {}\n", failed.join(", ")));
+        }
+
+        Self {
+            summary: format!("Proxy profile: {}", profile.unwrap_or("direct")),
+            body: body.trim_end().to_string(),
+            actions: vec![(ACTION_UNDO, "Undo"), (ACTION_PIN, "Pin")],
+        }
+    }
+
+    /// Sends the notification and returns the id assigned by the server.
+    pub fn send(&self, conn: &LocalConnection) -> Result<u32, dbus::Error> {
+        let actions = self
+            .actions
+            .iter()
+            .flat_map(|(key, label)| [*key, *label])
+            .collect::<Vec<&str>>();
+
+        let proxy = conn.with_proxy(
+            NOTIFICATIONS_NAME,
+            NOTIFICATIONS_PATH,
+            Duration::from_secs(5),
+        );
+        let (id,): (u32,) = proxy.method_call(
+            NOTIFICATIONS_INTERFACE,
+            "Notify",
+            (
+                "auto-proxy",
+                0u32,
+                "network-wireless",
+                self.summary.as_str(),
+                self.body.as_str(),
+                actions,
+                PropMap::new(),
+                -1i32,
+            ),
+        )?;
+
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbus::channel::{Channel, MatchingReceiver, Sender};
+    use dbus::message::MatchRule;
+    use std::cell::Cell;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    use std::rc::Rc;
+    use std::sync::mpsc;
+    use std::thread;
+
+    fn connect(address: &str) -> LocalConnection {
+        let mut channel = Channel::open_private(address).unwrap();
+        channel.register().unwrap();
+        LocalConnection::from(channel)
+    }
+
+    #[test]
+    fn test_profile_switched() {
+        let report = ApplyReport {
+            succeeded: vec!["bash", "git"],
+            failed: vec![("apt", "Permission denied".to_string())],
+            ..Default::default()
+        };
+
+        let notification = Notification::profile_switched(Some("office"), &report);
+        assert_eq!(notification.summary, "Proxy profile: office");
+        assert_eq!(
+            notification.body,
+            "Applied to: bash, git\nFailed: apt (Permission denied)"
+        );
+    }
+
+    #[test]
+    fn test_send_to_stub_server() {
+        // A private bus keeps the test away from the desktop's notification
+        // daemon; skip when dbus-daemon is not installed.
+        let mut bus = match Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(bus) => bus,
+            Err(_) => {
+                eprintln!("dbus-daemon not found, skipping");
+                return;
+            }
+        };
+        let mut address = String::new();
+        BufReader::new(bus.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        let address = address.trim().to_string();
+
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (received_tx, received_rx) = mpsc::channel();
+        let server_address = address.clone();
+        let server = thread::spawn(move || {
+            let conn = connect(&server_address);
+            conn.request_name(NOTIFICATIONS_NAME, false, true, true)
+                .unwrap();
+            let handled = Rc::new(Cell::new(false));
+            let handled_notify = handled.clone();
+            conn.start_receive(
+                MatchRule::new_method_call().with_interface(NOTIFICATIONS_INTERFACE),
+                Box::new(move |msg, conn| {
+                    let (_, _, _, summary, body, actions): (
+                        String,
+                        u32,
+                        String,
+                        String,
+                        String,
+                        Vec<String>,
+                    ) = msg.read_all().unwrap();
+                    received_tx.send((summary, body, actions)).unwrap();
+                    let _ = conn.send(msg.method_return().append1(42u32));
+                    handled_notify.set(true);
+                    true
+                }),
+            );
+            ready_tx.send(()).unwrap();
+            while !handled.get() {
+                conn.process(Duration::from_secs(5)).unwrap();
+            }
+        });
+        ready_rx.recv().unwrap();
+
+        let notification = Notification::profile_switched(
+            Some("office"),
+            &ApplyReport {
+                succeeded: vec!["bash"],
+                ..Default::default()
+            },
+        );
+        let id = notification.send(&connect(&address)).unwrap();
+        server.join().unwrap();
+        bus.kill().unwrap();
+        bus.wait().unwrap();
+
+        assert_eq!(id, 42);
+        let (summary, body, actions) = received_rx.recv().unwrap();
+        assert_eq!(summary, "Proxy profile: office");
+        assert_eq!(body, "Applied to: bash");
+        assert_eq!(actions, ["undo", "Undo", "pin", "Pin"]);
+    }
+}
@@ -12,6 +12,7 @@ pub enum ProxyProtocol {
 }
 
 impl ProxyProtocol {
+    #[allow(dead_code)]
     pub fn iter() -> impl Iterator<Item = &'static str> {
         ["http", "https", "ftp", "socks5"].iter().copied()
     }
@@ -52,9 +53,55 @@ pub struct ProxyAuth {
 }
 
 impl ProxyAuth {
+    const BASE64: &'static [u8] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
     pub fn new(username: String, password: String) -> Self {
         Self { username, password }
     }
+
+    /// The credentials as a `Proxy-Authorization` header value.
+    pub fn basic(&self) -> String {
+        let credentials = format!("{}:{}", self.username, self.password);
+        let mut encoded = String::new();
+        for chunk in credentials.as_bytes().chunks(3) {
+            let bytes = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let triple = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+            for index in 0..4 {
+                if index <= chunk.len() {
+                    encoded.push(Self::BASE64[(triple >> (18 - 6 * index) & 63) as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+        format!("Basic {}", encoded)
+    }
+
+    /// Parses a `Basic` header value as produced by [`ProxyAuth::basic`].
+    pub fn from_basic(authorization: &str) -> Option<Self> {
+        let encoded = authorization.strip_prefix("Basic ")?.trim_end_matches('=');
+        let mut decoded = Vec::new();
+        let mut buffer = 0u32;
+        let mut bits = 0;
+        for byte in encoded.bytes() {
+            let value = Self::BASE64.iter().position(|&digit| digit == byte)? as u32;
+            buffer = buffer << 6 | value;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                decoded.push((buffer >> bits) as u8);
+                buffer &= (1 << bits) - 1;
+            }
+        }
+        let credentials = String::from_utf8(decoded).ok()?;
+        let (username, password) = credentials.split_once(':')?;
+        Some(Self::new(username.to_string(), password.to_string()))
+    }
 }
 
 impl Clone for ProxyAuth {
@@ -73,9 +120,23 @@ pub struct ProxySettings {
     pub auth: Option<ProxyAuth>,
     pub protocols: Vec<ProxyProtocol>,
     pub no_proxy: Vec<String>,
+    /// A PAC file, used instead of `host` by the targets that support
+    /// automatic configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pac_url: Option<String>,
 }
 
 impl ProxySettings {
+    /// Whether the proxy speaks nothing but SOCKS, so that clients have to
+    /// talk SOCKS to it rather than HTTP.
+    pub fn socks_only(&self) -> bool {
+        !self.protocols.is_empty()
+            && self
+                .protocols
+                .iter()
+                .all(|protocol| *protocol == ProxyProtocol::Socks)
+    }
+
     pub fn new(
         host: String,
         port: String,
@@ -89,6 +150,7 @@ impl ProxySettings {
             auth,
             protocols,
             no_proxy,
+            pac_url: None,
         }
     }
 }
@@ -96,43 +158,31 @@ impl ProxySettings {
 impl fmt::Display for ProxySettings {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut proxy = String::new();
-        if self.auth.is_some() {
+        if let Some(auth) = &self.auth {
             for protocol in &self.protocols {
                 match protocol {
                     ProxyProtocol::Http => {
                         proxy.push_str(&format!(
                             "http://{}:{}@{}:{}\n",
-                            self.auth.as_ref().unwrap().username,
-                            self.auth.as_ref().unwrap().password,
-                            self.host,
-                            self.port
+                            auth.username, auth.password, self.host, self.port
                         ));
                     }
                     ProxyProtocol::Https => {
                         proxy.push_str(&format!(
                             "https://{}:{}@{}:{}\n",
-                            self.auth.as_ref().unwrap().username,
-                            self.auth.as_ref().unwrap().password,
-                            self.host,
-                            self.port
+                            auth.username, auth.password, self.host, self.port
                         ));
                     }
                     ProxyProtocol::Ftp => {
                         proxy.push_str(&format!(
                             "ftp://{}:{}@{}:{}\n",
-                            self.auth.as_ref().unwrap().username,
-                            self.auth.as_ref().unwrap().password,
-                            self.host,
-                            self.port
+                            auth.username, auth.password, self.host, self.port
                         ));
                     }
                     ProxyProtocol::Socks => {
                         proxy.push_str(&format!(
                             "socks://{}:{}@{}:{}\n",
-                            self.auth.as_ref().unwrap().username,
-                            self.auth.as_ref().unwrap().password,
-                            self.host,
-                            self.port
+                            auth.username, auth.password, self.host, self.port
                         ));
                     }
                 }
@@ -164,6 +214,7 @@ pub trait Proxy {
     fn new(host: String, port: String, auth: Option<ProxyAuth>) -> Self
     where
         Self: Sized;
+    #[allow(dead_code)]
     fn protocol(&self) -> ProxyProtocol;
     fn host(&self) -> &str;
     fn port(&self) -> &str;
@@ -239,8 +290,11 @@ impl Proxy for GeneralProxy {
         Self: Sized,
     {
         let url = url::Url::parse(proxy)?;
-        let host = url.host_str().unwrap().to_string();
-        let port = url.port().unwrap().to_string();
+        let host = url.host_str().ok_or("proxy URL has no host")?.to_string();
+        let port = url
+            .port_or_known_default()
+            .ok_or("proxy URL has no port")?
+            .to_string();
         let auth = match (url.username(), url.password()) {
             (username, Some(password)) => {
                 Some(ProxyAuth::new(username.to_string(), password.to_string()))
@@ -257,6 +311,7 @@ impl Proxy for GeneralProxy {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct HttpProxy {
     pub host: String,
@@ -304,8 +359,11 @@ impl Proxy for HttpProxy {
         Self: Sized,
     {
         let url = url::Url::parse(proxy)?;
-        let host = url.host_str().unwrap().to_string();
-        let port = url.port().unwrap().to_string();
+        let host = url.host_str().ok_or("proxy URL has no host")?.to_string();
+        let port = url
+            .port_or_known_default()
+            .ok_or("proxy URL has no port")?
+            .to_string();
         let auth = match (url.username(), url.password()) {
             (username, Some(password)) => {
                 Some(ProxyAuth::new(username.to_string(), password.to_string()))
@@ -317,6 +375,7 @@ impl Proxy for HttpProxy {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct HttpsProxy {
     pub host: String,
@@ -364,8 +423,11 @@ impl Proxy for HttpsProxy {
         Self: Sized,
     {
         let url = url::Url::parse(proxy)?;
-        let host = url.host_str().unwrap().to_string();
-        let port = url.port().unwrap().to_string();
+        let host = url.host_str().ok_or("proxy URL has no host")?.to_string();
+        let port = url
+            .port_or_known_default()
+            .ok_or("proxy URL has no port")?
+            .to_string();
         let auth = match (url.username(), url.password()) {
             (username, Some(password)) => {
                 Some(ProxyAuth::new(username.to_string(), password.to_string()))
@@ -377,6 +439,7 @@ impl Proxy for HttpsProxy {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct FtpProxy {
     pub host: String,
@@ -424,8 +487,11 @@ impl Proxy for FtpProxy {
         Self: Sized,
     {
         let url = url::Url::parse(proxy)?;
-        let host = url.host_str().unwrap().to_string();
-        let port = url.port().unwrap().to_string();
+        let host = url.host_str().ok_or("proxy URL has no host")?.to_string();
+        let port = url
+            .port_or_known_default()
+            .ok_or("proxy URL has no port")?
+            .to_string();
         let auth = match (url.username(), url.password()) {
             (username, Some(password)) => {
                 Some(ProxyAuth::new(username.to_string(), password.to_string()))
@@ -437,6 +503,7 @@ impl Proxy for FtpProxy {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SocksProxy {
     pub host: String,
@@ -484,8 +551,11 @@ impl Proxy for SocksProxy {
         Self: Sized,
     {
         let url = url::Url::parse(proxy)?;
-        let host = url.host_str().unwrap().to_string();
-        let port = url.port().unwrap().to_string();
+        let host = url.host_str().ok_or("proxy URL has no host")?.to_string();
+        let port = url
+            .port_or_known_default()
+            .ok_or("proxy URL has no port")?
+            .to_string();
         let auth = match (url.username(), url.password()) {
             (username, Some(password)) => {
                 Some(ProxyAuth::new(username.to_string(), password.to_string()))
@@ -496,3 +566,27 @@ impl Proxy for SocksProxy {
         Ok(Box::new(SocksProxy { host, port, auth }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_authorization() {
+        for (username, password) in [
+            ("a", ""),
+            ("ab", "c"),
+            ("alice", "s3cret"),
+            ("bob", "pa:ss"),
+        ] {
+            let auth = ProxyAuth::new(username.to_string(), password.to_string());
+            let decoded = ProxyAuth::from_basic(&auth.basic()).unwrap();
+            assert_eq!(decoded.username, username);
+            assert_eq!(decoded.password, password);
+        }
+        assert_eq!(
+            ProxyAuth::new("alice".to_string(), "s3cret".to_string()).basic(),
+            "Basic YWxpY2U6czNjcmV0"
+        );
+    }
+}
@@ -52,16 +52,19 @@ impl ProxyProfile {
         Ok(Self::new(name, proxy_settings, auto_apply_networks))
     }
 
+    #[allow(dead_code)]
     pub fn from_string(profile_content: &str) -> Result<Self, Box<dyn Error>> {
         let profile: Self = serde_yaml::from_str(profile_content)?;
         Ok(profile)
     }
 
+    #[allow(dead_code)]
     pub fn to_string(&self) -> Result<String, Box<dyn Error>> {
         let profile_content = serde_yaml::to_string(&self)?;
         Ok(profile_content)
     }
 
+    #[allow(dead_code)]
     pub fn to_file(&self, profile_file: &str) -> Result<(), Box<dyn Error>> {
         let mut file = FsFile::create(profile_file)?;
 
@@ -72,6 +75,7 @@ impl ProxyProfile {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub fn update_profile(&self) -> Result<(), Box<dyn Error>> {
         let profile_dir = dirs::home_dir().unwrap().join(".auto-proxy/profiles");
 
@@ -86,6 +90,7 @@ impl ProxyProfile {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub fn delete_profile(&self) -> Result<(), Box<dyn Error>> {
         let profile_dir = dirs::home_dir().unwrap().join(".auto-proxy/profiles");
 
@@ -96,6 +101,7 @@ impl ProxyProfile {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub fn list_profiles() -> Result<(), Box<dyn Error>> {
         let profile_dir = dirs::home_dir().unwrap().join(".auto-proxy/profiles");
 
@@ -112,6 +118,25 @@ impl ProxyProfile {
         Ok(())
     }
 
+    pub fn load_all() -> Result<Vec<Self>, Box<dyn Error>> {
+        let profile_dir = dirs::home_dir().unwrap().join(".auto-proxy/profiles");
+
+        if !profile_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut profiles = Vec::new();
+        for profile_file in std::fs::read_dir(profile_dir)? {
+            let profile_path = profile_file?.path();
+            if profile_path.extension().is_some_and(|ext| ext == "yaml") {
+                profiles.push(Self::from_file(profile_path.to_str().unwrap())?);
+            }
+        }
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(profiles)
+    }
+
     pub fn get_profile(profile_name: &str) -> Result<Self, Box<dyn Error>> {
         let profile_dir = dirs::home_dir().unwrap().join(".auto-proxy/profiles");
 
@@ -1,35 +1,317 @@
+use std::env;
 use std::error::Error;
 use std::fs::{self, File};
 use std::io::prelude::*;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
-fn do_setup() -> Result<(), Box<dyn Error>> {
-    create_config_files()?;
-    Ok(())
+use crate::config::AutoProxyConfig;
+
+const SYSTEMD_UNIT_NAME: &str = "auto-proxy.service";
+const SYSTEMD_USER_DIR: &str = ".config/systemd/user";
+const DISPATCHER_SCRIPT: &str = "/etc/NetworkManager/dispatcher.d/90-auto-proxy";
+
+/// Installs auto-proxy's files below `root`, which is `/` outside of tests.
+pub struct Installer {
+    root: PathBuf,
+    home: PathBuf,
+    exe: PathBuf,
 }
 
-fn check_config_files() -> Result<(), Box<dyn Error>> {
-    let config_dir = dirs::home_dir().unwrap().join(".auto-proxy/config");
-    let config_file = config_dir.join("config.yaml");
-    let profiles_file = config_dir.join("proxy-profiles.yaml");
+impl Installer {
+    pub fn new(root: Option<PathBuf>) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            root: root.unwrap_or_else(|| PathBuf::from("/")),
+            home: home_dir()?,
+            exe: env::current_exe()?,
+        })
+    }
+
+    fn is_system_root(&self) -> bool {
+        self.root == Path::new("/")
+    }
+
+    fn path(&self, path: &Path) -> PathBuf {
+        self.root.join(path.strip_prefix("/").unwrap_or(path))
+    }
+
+    fn config_dir(&self) -> PathBuf {
+        self.path(&self.home.join(".auto-proxy/config"))
+    }
 
-    if !config_file.exists() || !profiles_file.exists() {
-        return Err("Config files not found".into());
+    fn profiles_dir(&self) -> PathBuf {
+        self.path(&self.home.join(".auto-proxy/profiles"))
+    }
+
+    fn systemd_unit_file(&self) -> PathBuf {
+        self.path(&self.home.join(SYSTEMD_USER_DIR).join(SYSTEMD_UNIT_NAME))
+    }
+
+    fn dispatcher_script(&self) -> PathBuf {
+        self.path(Path::new(DISPATCHER_SCRIPT))
+    }
+
+    #[allow(dead_code)]
+    pub fn check_config_files(&self) -> Result<(), Box<dyn Error>> {
+        let config_file = self.config_dir().join("config.yaml");
+
+        if !config_file.exists() || !self.profiles_dir().exists() {
+            return Err("Config files not found".into());
+        }
+
+        Ok(())
+    }
+
+    /// Writes the default configuration, keeping an existing one untouched.
+    pub fn create_config_files(&self) -> Result<(), Box<dyn Error>> {
+        let config_dir = self.config_dir();
+        fs::create_dir_all(&config_dir)?;
+        fs::create_dir_all(self.profiles_dir())?;
+
+        let config_file = config_dir.join("config.yaml");
+        if !config_file.exists() {
+            let mut file = File::create(&config_file)?;
+            file.write_all(serde_yaml::to_string(&AutoProxyConfig::default())?.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn render_systemd_unit(&self) -> String {
+        format!(
+            "[Unit]\n\
+             Description=Apply proxy settings automatically on network change\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             ExecStart={} auto-apply\n\
+             Restart=on-failure\n\
+             \n\
+             [Install]\n\
+             WantedBy=default.target\n",
+            systemd_quote(&self.exe.display().to_string())
+        )
+    }
+
+    /// The dispatcher runs as root, so it switches to the invoking user and
+    /// points them at their session bus before running a one-shot apply.
+    pub fn render_dispatcher_script(&self, user: &str, uid: u32) -> String {
+        format!(
+            "#!/bin/sh\n\
+             # Installed by auto-proxy setup, removed by `auto-proxy setup --uninstall`.\n\
+             case \"$2\" in\n\
+             \x20   up|down|connectivity-change)\n\
+             \x20       runuser -u {user} -- env \
+             DBUS_SESSION_BUS_ADDRESS=unix:path=/run/user/{uid}/bus \
+             XDG_RUNTIME_DIR=/run/user/{uid} \
+             {exe} auto-apply --once\n\
+             \x20       ;;\n\
+             esac\n",
+            user = user,
+            uid = uid,
+            exe = shell_quote(&self.exe.display().to_string())
+        )
+    }
+
+    pub fn install_systemd_unit(&self) -> Result<(), Box<dyn Error>> {
+        let unit_file = self.systemd_unit_file();
+        fs::create_dir_all(unit_file.parent().unwrap())?;
+        fs::write(&unit_file, self.render_systemd_unit())?;
+
+        if self.is_system_root() {
+            systemctl_user(&["daemon-reload"])?;
+            systemctl_user(&["enable", "--now", SYSTEMD_UNIT_NAME])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn install_dispatcher(&self) -> Result<(), Box<dyn Error>> {
+        let user = current_user()?;
+        let uid = match env::var("SUDO_UID") {
+            Ok(uid) => uid.parse()?,
+            Err(_) => fs::metadata(&self.home)?.uid(),
+        };
+
+        let script = self.dispatcher_script();
+        fs::create_dir_all(script.parent().unwrap())?;
+        fs::write(&script, self.render_dispatcher_script(&user, uid))?;
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755))?;
+
+        Ok(())
+    }
+
+    /// Removes whichever of the systemd unit and dispatcher script exist.
+    pub fn uninstall(&self) -> Result<(), Box<dyn Error>> {
+        let unit_file = self.systemd_unit_file();
+        if unit_file.exists() {
+            if self.is_system_root() {
+                systemctl_user(&["disable", "--now", SYSTEMD_UNIT_NAME])?;
+            }
+            fs::remove_file(&unit_file)?;
+            if self.is_system_root() {
+                systemctl_user(&["daemon-reload"])?;
+            }
+        }
+
+        let script = self.dispatcher_script();
+        if script.exists() {
+            fs::remove_file(&script)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The user that invoked setup, looking through `sudo`.
+fn current_user() -> Result<String, Box<dyn Error>> {
+    for var in ["SUDO_USER", "USER"] {
+        match env::var(var) {
+            Ok(user) if !user.is_empty() => return Ok(user),
+            _ => continue,
+        }
+    }
+
+    let output = Command::new("id").arg("-nu").output()?;
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// The invoking user's home directory, looking through `sudo` like
+/// `current_user`.
+fn home_dir() -> Result<PathBuf, Box<dyn Error>> {
+    match env::var("SUDO_USER") {
+        Ok(user) if !user.is_empty() => {
+            let output = Command::new("getent").args(["passwd", &user]).output()?;
+            passwd_home(&String::from_utf8(output.stdout)?)
+                .ok_or_else(|| format!("Could not determine the home directory of {}", user).into())
+        }
+        _ => Ok(dirs::home_dir().ok_or("Could not determine home directory")?),
+    }
+}
+
+/// The home directory field of a `passwd` entry.
+fn passwd_home(entry: &str) -> Option<PathBuf> {
+    entry
+        .trim()
+        .split(':')
+        .nth(5)
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+fn systemctl_user(args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let status = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .status()?;
+
+    if !status.success() {
+        return Err(format!("systemctl --user {} failed", args.join(" ")).into());
     }
 
     Ok(())
 }
 
-fn create_config_files() -> std::io::Result<()> {
-    let config_dir = dirs::home_dir().unwrap().join(".auto-proxy/config");
-    fs::create_dir_all(&config_dir)?;
+pub struct SetupOptions {
+    pub root: Option<PathBuf>,
+    pub systemd: bool,
+    pub dispatcher: bool,
+    pub uninstall: bool,
+}
+
+pub fn do_setup(options: SetupOptions) -> Result<(), Box<dyn Error>> {
+    let installer = Installer::new(options.root)?;
 
-    let config_file = config_dir.join("config.yaml");
-    let mut file = File::create(&config_file)?;
-    file.write_all(b"config file content")?;
+    if options.uninstall {
+        return installer.uninstall();
+    }
 
-    let profiles_file = config_dir.join("proxy-profiles.yaml");
-    let mut file = File::create(&profiles_file)?;
-    file.write_all(b"proxy profiles content")?;
+    installer.create_config_files()?;
+    if options.systemd {
+        installer.install_systemd_unit()?;
+    }
+    if options.dispatcher {
+        installer.install_dispatcher()?;
+    }
 
     Ok(())
 }
+
+/// Quotes `value` for `sh`, closing the quotes around any embedded `'`.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quotes `value` as a single word of a systemd command line, escaping the
+/// specifier and variable expansions it would otherwise apply.
+fn systemd_quote(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+            .replace('$', "$$")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_and_uninstall() {
+        let root = env::temp_dir().join(format!("auto-proxy-setup-{}", std::process::id()));
+        let installer = Installer::new(Some(root.clone())).unwrap();
+
+        installer.create_config_files().unwrap();
+        installer.check_config_files().unwrap();
+        installer.install_systemd_unit().unwrap();
+        installer.install_dispatcher().unwrap();
+
+        let unit = fs::read_to_string(installer.systemd_unit_file()).unwrap();
+        assert!(unit.contains(" auto-apply\n"));
+        let script = installer.dispatcher_script();
+        assert!(script.starts_with(&root));
+        assert!(fs::read_to_string(&script)
+            .unwrap()
+            .contains("auto-apply --once"));
+        assert_eq!(
+            fs::metadata(&script).unwrap().permissions().mode() & 0o777,
+            0o755
+        );
+
+        installer.uninstall().unwrap();
+        assert!(!installer.systemd_unit_file().exists());
+        assert!(!script.exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_dispatcher_script_quotes_exe() {
+        let mut installer = Installer::new(Some(env::temp_dir())).unwrap();
+        installer.exe = PathBuf::from("/opt/Bob's Tools/auto-proxy");
+        assert!(installer
+            .render_dispatcher_script("bob", 1000)
+            .contains(" '/opt/Bob'\\''s Tools/auto-proxy' auto-apply --once\n"));
+        assert!(installer
+            .render_systemd_unit()
+            .contains("\nExecStart=\"/opt/Bob's Tools/auto-proxy\" auto-apply\n"));
+
+        installer.exe = PathBuf::from("/opt/100% \"tools\"/auto-proxy");
+        assert!(installer
+            .render_systemd_unit()
+            .contains("\nExecStart=\"/opt/100%% \\\"tools\\\"/auto-proxy\" auto-apply\n"));
+    }
+
+    #[test]
+    fn test_passwd_home() {
+        assert_eq!(
+            passwd_home("bob:x:1000:1000:Bob,,,:/home/bob:/bin/bash\n"),
+            Some(PathBuf::from("/home/bob"))
+        );
+        assert_eq!(passwd_home(""), None);
+    }
+}
@@ -0,0 +1,117 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// Runtime state shared by the daemon and one-shot invocations, persisted
+/// so that restarts do not re-apply a profile that is already active.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    pub active_profile: Option<String>,
+    pub paused: bool,
+    pub pin: Option<Pin>,
+    /// Gradle projects the daemon was started with, beyond the configured
+    /// ones.
+    pub gradle_projects: Vec<PathBuf>,
+}
+
+/// A profile chosen by hand that suspends automatic selection until it
+/// expires, or until the network it was pinned on changes.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Pin {
+    pub profile: String,
+    /// Unix time in seconds after which the pin expires.
+    pub until: Option<u64>,
+    pub until_network_change: bool,
+    pub network: Option<String>,
+}
+
+impl Pin {
+    pub fn is_active(&self, now: u64, network: Option<&str>) -> bool {
+        if self.until.is_some_and(|until| now >= until) {
+            return false;
+        }
+
+        !self.until_network_change || self.network.as_deref() == network
+    }
+
+    pub fn describe(&self, now: u64) -> String {
+        let mut description = self.profile.clone();
+        if let Some(until) = self.until {
+            let remaining = until.saturating_sub(now);
+            description.push_str(&format!(
+                " for another {}h {}m",
+                remaining / 3600,
+                remaining % 3600 / 60
+            ));
+        }
+        if self.until_network_change {
+            description.push_str(" until the network changes");
+        }
+        description
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+impl State {
+    pub fn state_file() -> PathBuf {
+        dirs::home_dir().unwrap().join(".auto-proxy/state.yaml")
+    }
+
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let state_file = Self::state_file();
+        if !state_file.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(state_file)?;
+        let state = serde_yaml::from_str(&content)?;
+        Ok(state)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let state_file = Self::state_file();
+        if let Some(parent) = state_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(state_file, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_is_active() {
+        let timed = Pin {
+            profile: "direct".to_string(),
+            until: Some(1_000),
+            until_network_change: false,
+            network: None,
+        };
+        assert!(timed.is_active(999, Some("Corp")));
+        assert!(!timed.is_active(1_000, Some("Corp")));
+
+        let network_bound = Pin {
+            profile: "direct".to_string(),
+            until: None,
+            until_network_change: true,
+            network: Some("Corp".to_string()),
+        };
+        assert!(network_bound.is_active(u64::MAX, Some("Corp")));
+        assert!(!network_bound.is_active(0, Some("Cafe")));
+        assert!(!network_bound.is_active(0, None));
+    }
+}
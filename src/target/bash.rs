@@ -0,0 +1,104 @@
+use crate::{
+    proxy::ProxySettings,
+    setup::shell_quote,
+    target::{environment::Environment, managed_block, settings_from_url, ProxyTarget},
+};
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// A managed block of `export`s in `~/.bashrc`, for shells started outside
+/// the systemd user session, such as over SSH.
+pub struct Bash {
+    bashrc_file: PathBuf,
+}
+
+impl Bash {
+    pub fn new(bashrc_file: PathBuf) -> Self {
+        Self { bashrc_file }
+    }
+
+    pub fn bashrc_file() -> PathBuf {
+        dirs::home_dir().unwrap().join(".bashrc")
+    }
+
+    fn generate_bashrc_content(setting: &ProxySettings) -> String {
+        Environment::variables(setting)
+            .iter()
+            .map(|(name, value)| format!("export {}={}\n", name, shell_quote(value)))
+            .collect()
+    }
+
+    fn parse_bashrc_content(content: &str) -> Option<ProxySettings> {
+        let variable = |name: &str| {
+            content.lines().find_map(|line| {
+                let (key, value) = line.trim().strip_prefix("export ")?.split_once('=')?;
+                (key == name).then(|| value.trim_matches('\''))
+            })
+        };
+        let proxy = variable("https_proxy").or_else(|| variable("http_proxy"))?;
+        settings_from_url(proxy, variable("no_proxy"))
+    }
+}
+
+impl ProxyTarget for Bash {
+    fn name(&self) -> &'static str {
+        "bash"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.bashrc_file).ok()?;
+        Self::parse_bashrc_content(managed_block::get(&content, "#")?)
+            .map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.bashrc_file).unwrap_or_default();
+        fs::write(
+            &self.bashrc_file,
+            managed_block::replace(&content, "#", &Self::generate_bashrc_content(setting)),
+        )?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.bashrc_file) {
+            let updated = managed_block::remove(&content, "#");
+            if updated != content {
+                fs::write(&self.bashrc_file, updated)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bashrc_block() {
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            no_proxy: vec!["localhost".to_string()],
+            ..Default::default()
+        };
+        let content = Bash::generate_bashrc_content(&setting);
+        assert!(content.starts_with(
+            "export http_proxy='http://proxy.corp:3128'\n\
+             export HTTP_PROXY='http://proxy.corp:3128'\n"
+        ));
+        assert!(content.ends_with("export NO_PROXY='localhost'\n"));
+
+        let parsed = Bash::parse_bashrc_content(&content).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.no_proxy, ["localhost"]);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    config::CargoConfig,
+    proxy::ProxySettings,
+    target::{proxy_url, settings_from_url, ProxyTarget},
+};
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use toml_edit::{table, value, DocumentMut, Item};
+
+/// The `[http]` table of Cargo's user configuration.
+pub struct Cargo {
+    config_file: PathBuf,
+    check_revoke: Option<bool>,
+    cainfo: Option<String>,
+}
+
+impl Cargo {
+    pub fn new(config_file: PathBuf, config: &CargoConfig) -> Self {
+        Self {
+            config_file,
+            check_revoke: config.check_revoke,
+            cainfo: config.cainfo.clone(),
+        }
+    }
+
+    pub fn config_file() -> PathBuf {
+        let cargo_home = match env::var_os("CARGO_HOME") {
+            Some(cargo_home) => PathBuf::from(cargo_home),
+            None => dirs::home_dir().unwrap().join(".cargo"),
+        };
+        cargo_home.join("config.toml")
+    }
+
+    /// The keys of `[http]` written by `set`, and removed again by `unset`.
+    fn owned_keys(&self) -> Vec<&'static str> {
+        let mut keys = vec!["proxy"];
+        if self.check_revoke.is_some() {
+            keys.push("check-revoke");
+        }
+        if self.cainfo.is_some() {
+            keys.push("cainfo");
+        }
+        keys
+    }
+
+    fn set_http(&self, content: &str, setting: &ProxySettings) -> Result<String, Box<dyn Error>> {
+        let mut document = content.parse::<DocumentMut>()?;
+        let http = document.entry("http").or_insert(table());
+
+        http["proxy"] = value(proxy_url(setting));
+        if let Some(check_revoke) = self.check_revoke {
+            http["check-revoke"] = value(check_revoke);
+        }
+        if let Some(cainfo) = &self.cainfo {
+            http["cainfo"] = value(cainfo.as_str());
+        }
+
+        Ok(document.to_string())
+    }
+
+    fn unset_http(&self, content: &str) -> Result<String, Box<dyn Error>> {
+        let mut document = content.parse::<DocumentMut>()?;
+        if let Some(http) = document.get_mut("http").and_then(Item::as_table_like_mut) {
+            for key in self.owned_keys() {
+                http.remove(key);
+            }
+            if http.is_empty() {
+                document.remove("http");
+            }
+        }
+
+        Ok(document.to_string())
+    }
+
+    fn get_http(content: &str) -> Option<ProxySettings> {
+        let document = content.parse::<DocumentMut>().ok()?;
+        let proxy = document.get("http")?.get("proxy")?.as_str()?;
+        // Cargo also accepts a bare `host:port`.
+        if proxy.contains("://") {
+            settings_from_url(proxy, None)
+        } else {
+            settings_from_url(&format!("http://{}", proxy), None)
+        }
+    }
+}
+
+impl ProxyTarget for Cargo {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.config_file).ok()?;
+        Self::get_http(&content).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.config_file).unwrap_or_default();
+        if let Some(parent) = self.config_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.config_file, self.set_http(&content, setting)?)?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.config_file) {
+            fs::write(&self.config_file, self.unset_http(&content)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_table() {
+        let content = "# Shared build settings\n\
+                       [build]\n\
+                       jobs = 8 # leave some cores free\n\
+                       \n\
+                       [http]\n\
+                       timeout = 60\n";
+        let cargo = Cargo::new(
+            PathBuf::new(),
+            &CargoConfig {
+                check_revoke: Some(false),
+                cainfo: Some("/etc/ssl/certs/corp.pem".to_string()),
+            },
+        );
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            ..Default::default()
+        };
+
+        let updated = cargo.set_http(content, &setting).unwrap();
+        assert_eq!(
+            updated,
+            "# Shared build settings\n\
+             [build]\n\
+             jobs = 8 # leave some cores free\n\
+             \n\
+             [http]\n\
+             timeout = 60\n\
+             proxy = \"http://proxy.corp:3128\"\n\
+             check-revoke = false\n\
+             cainfo = \"/etc/ssl/certs/corp.pem\"\n"
+        );
+
+        let parsed = Cargo::get_http(&updated).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.port, "3128");
+
+        assert_eq!(cargo.unset_http(&updated).unwrap(), content);
+        assert_eq!(
+            cargo
+                .unset_http(&cargo.set_http("", &setting).unwrap())
+                .unwrap(),
+            ""
+        );
+        assert_eq!(
+            Cargo::get_http("[http]\nproxy = \"proxy.corp:8080\"\n")
+                .unwrap()
+                .port,
+            "8080"
+        );
+    }
+}
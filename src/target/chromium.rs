@@ -0,0 +1,315 @@
+use crate::{
+    proxy::{ProxyProtocol, ProxySettings},
+    target::{command_exists, managed_block, parse_json_object, ProxyTarget},
+};
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+/// Where Chrome-family browsers and Electron apps read their proxy from.
+#[derive(Debug, PartialEq)]
+pub enum ChromiumConfig {
+    /// Managed policy files, owned by auto-proxy as a whole.
+    Policies(Vec<PathBuf>),
+    /// A managed block of command-line switches in `*-flags.conf` files, for
+    /// desktops whose proxy settings these apps do not follow.
+    Flags(Vec<PathBuf>),
+}
+
+pub struct Chromium {
+    config: ChromiumConfig,
+}
+
+impl Chromium {
+    const POLICY_DIRS: [(&'static str, &'static [&'static str]); 2] = [
+        (
+            "/etc/chromium/policies/managed",
+            &["chromium", "chromium-browser"],
+        ),
+        (
+            "/etc/opt/chrome/policies/managed",
+            &["google-chrome", "google-chrome-stable"],
+        ),
+    ];
+    const FLAGS_FILES: [(&'static str, &'static [&'static str]); 3] = [
+        ("chromium-flags.conf", &["chromium", "chromium-browser"]),
+        (
+            "chrome-flags.conf",
+            &["google-chrome", "google-chrome-stable"],
+        ),
+        ("electron-flags.conf", &["electron"]),
+    ];
+
+    pub fn new(config: ChromiumConfig) -> Self {
+        Self { config }
+    }
+
+    /// The policy file of each installed browser.
+    pub fn policy_files() -> Vec<PathBuf> {
+        Self::POLICY_DIRS
+            .iter()
+            .filter(|(_, commands)| commands.iter().any(|command| command_exists(command)))
+            .map(|(dir, _)| PathBuf::from(dir).join("auto-proxy.json"))
+            .collect()
+    }
+
+    /// The usual flags files of the installed browsers, plus every existing
+    /// `*-flags.conf`, such as those the user keeps for an Electron app.
+    pub fn flags_files() -> Vec<PathBuf> {
+        let config_dir = dirs::config_dir().unwrap();
+        let mut files = Self::FLAGS_FILES
+            .iter()
+            .filter(|(_, commands)| commands.iter().any(|command| command_exists(command)))
+            .map(|(name, _)| config_dir.join(name))
+            .collect::<Vec<PathBuf>>();
+        if let Ok(entries) = fs::read_dir(&config_dir) {
+            for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+                let is_flags = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.ends_with("-flags.conf"));
+                if is_flags && !files.contains(&path) {
+                    files.push(path);
+                }
+            }
+        }
+        files
+    }
+
+    /// The proxy as Chrome's `--proxy-server` expects it. Credentials are
+    /// left out since Chrome only takes them interactively.
+    fn proxy_server(setting: &ProxySettings) -> String {
+        let socks_only = setting.socks_only();
+        let scheme = if socks_only { "socks5" } else { "http" };
+        format!("{}://{}:{}", scheme, setting.host, setting.port)
+    }
+
+    fn generate_policy(setting: &ProxySettings) -> Value {
+        match &setting.pac_url {
+            Some(pac_url) => json!({
+                "ProxyMode": "pac_script",
+                "ProxyPacUrl": pac_url,
+            }),
+            None => json!({
+                "ProxyMode": "fixed_servers",
+                "ProxyServer": Self::proxy_server(setting),
+                "ProxyBypassList": setting.no_proxy.join(";"),
+            }),
+        }
+    }
+
+    fn parse_policy(content: &str) -> Option<ProxySettings> {
+        let policy = parse_json_object(content).ok()?;
+        match policy.get("ProxyMode")?.as_str()? {
+            "pac_script" => Some(ProxySettings {
+                pac_url: Some(policy.get("ProxyPacUrl")?.as_str()?.to_string()),
+                ..Default::default()
+            }),
+            "fixed_servers" => Self::parse_proxy(
+                policy.get("ProxyServer")?.as_str()?,
+                policy.get("ProxyBypassList").and_then(Value::as_str),
+            ),
+            _ => None,
+        }
+    }
+
+    fn generate_flags(setting: &ProxySettings) -> String {
+        match &setting.pac_url {
+            Some(pac_url) => format!("--proxy-pac-url={}\n", pac_url),
+            None => {
+                let mut flags = format!("--proxy-server={}\n", Self::proxy_server(setting));
+                if !setting.no_proxy.is_empty() {
+                    flags.push_str(&format!(
+                        "--proxy-bypass-list={}\n",
+                        setting.no_proxy.join(";")
+                    ));
+                }
+                flags
+            }
+        }
+    }
+
+    fn parse_flags(content: &str) -> Option<ProxySettings> {
+        let flag = |name: &str| {
+            content
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(&format!("--{}=", name)))
+        };
+        if let Some(pac_url) = flag("proxy-pac-url") {
+            return Some(ProxySettings {
+                pac_url: Some(pac_url.to_string()),
+                ..Default::default()
+            });
+        }
+        Self::parse_proxy(flag("proxy-server")?, flag("proxy-bypass-list"))
+    }
+
+    fn parse_proxy(server: &str, bypass_list: Option<&str>) -> Option<ProxySettings> {
+        let (scheme, address) = server.split_once("://").unwrap_or(("http", server));
+        let (host, port) = address.rsplit_once(':')?;
+        Some(ProxySettings {
+            host: host.to_string(),
+            port: port.to_string(),
+            protocols: if scheme.starts_with("socks") {
+                vec![ProxyProtocol::Socks]
+            } else {
+                Vec::new()
+            },
+            no_proxy: bypass_list
+                .unwrap_or_default()
+                .split([';', ','])
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .map(String::from)
+                .collect(),
+            ..Default::default()
+        })
+    }
+}
+
+impl ProxyTarget for Chromium {
+    fn name(&self) -> &'static str {
+        "chromium"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let proxy_settings = match &self.config {
+            ChromiumConfig::Policies(paths) => paths
+                .iter()
+                .find_map(|path| Self::parse_policy(&fs::read_to_string(path).ok()?)),
+            ChromiumConfig::Flags(paths) => paths.iter().find_map(|path| {
+                let content = fs::read_to_string(path).ok()?;
+                Self::parse_flags(managed_block::get(&content, "#")?)
+            }),
+        }?;
+        Some(vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        match &self.config {
+            ChromiumConfig::Policies(paths) => {
+                let policy = serde_json::to_string_pretty(&Self::generate_policy(setting))? + "\n";
+                for path in paths {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(path, &policy)?;
+                }
+            }
+            ChromiumConfig::Flags(paths) => {
+                let flags = Self::generate_flags(setting);
+                for path in paths {
+                    let content = fs::read_to_string(path).unwrap_or_default();
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(path, managed_block::replace(&content, "#", &flags))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        match &self.config {
+            ChromiumConfig::Policies(paths) => {
+                for path in paths.iter().filter(|path| path.exists()) {
+                    fs::remove_file(path)?;
+                }
+            }
+            ChromiumConfig::Flags(paths) => {
+                for path in paths {
+                    if let Ok(content) = fs::read_to_string(path) {
+                        fs::write(path, managed_block::remove(&content, "#"))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn supports_pac(&self) -> bool {
+        true
+    }
+
+    fn follow_up(&self) -> Option<String> {
+        Some(
+            "Restart Chromium-based browsers and Electron apps to pick up the new proxy"
+                .to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn office_settings() -> ProxySettings {
+        ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            no_proxy: vec!["localhost".to_string(), ".corp".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = Chromium::generate_policy(&office_settings());
+        assert_eq!(
+            policy,
+            json!({
+                "ProxyMode": "fixed_servers",
+                "ProxyServer": "http://proxy.corp:3128",
+                "ProxyBypassList": "localhost;.corp",
+            })
+        );
+
+        let parsed = Chromium::parse_policy(&policy.to_string()).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.no_proxy, ["localhost", ".corp"]);
+
+        let pac = ProxySettings {
+            pac_url: Some("http://wpad.corp/proxy.pac".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Chromium::generate_policy(&pac),
+            json!({
+                "ProxyMode": "pac_script",
+                "ProxyPacUrl": "http://wpad.corp/proxy.pac",
+            })
+        );
+    }
+
+    #[test]
+    fn test_flags() {
+        let socks = ProxySettings {
+            protocols: vec![ProxyProtocol::Socks],
+            ..office_settings()
+        };
+        let flags = Chromium::generate_flags(&socks);
+        assert_eq!(
+            flags,
+            "--proxy-server=socks5://proxy.corp:3128\n\
+             --proxy-bypass-list=localhost;.corp\n"
+        );
+
+        let content = managed_block::replace("--enable-features=UseOzonePlatform\n", "#", &flags);
+        let parsed = Chromium::parse_flags(managed_block::get(&content, "#").unwrap()).unwrap();
+        assert_eq!(parsed.protocols, [ProxyProtocol::Socks]);
+        assert_eq!(parsed.port, "3128");
+        assert_eq!(
+            managed_block::remove(&content, "#"),
+            "--enable-features=UseOzonePlatform\n"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{
+    proxy::{ProxyAuth, ProxyProtocol, ProxySettings},
+    target::{jsonc, ProxyTarget},
+};
+
+use serde_json::{json, Value};
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// The `http.*` proxy settings of VS Code and its builds. Only the keys below
+/// are edited; `http.proxyStrictSSL` stays the user's choice since nothing
+/// in a profile says whether the proxy intercepts TLS.
+pub struct VSCode {
+    settings_files: Vec<PathBuf>,
+}
+
+impl VSCode {
+    const KEYS: [&'static str; 3] = ["http.proxy", "http.noProxy", "http.proxyAuthorization"];
+    const EDITIONS: [&'static str; 4] = ["Code", "Code - OSS", "VSCodium", "Code - Insiders"];
+    pub fn new(settings_files: Vec<PathBuf>) -> Self {
+        Self { settings_files }
+    }
+
+    /// User settings of each installed edition, and the machine settings of
+    /// the remote server.
+    pub fn settings_files() -> Vec<PathBuf> {
+        let config_dir = dirs::config_dir().unwrap();
+        let mut files = Self::EDITIONS
+            .iter()
+            .map(|edition| config_dir.join(edition))
+            .filter(|dir| dir.is_dir())
+            .map(|dir| dir.join("User/settings.json"))
+            .collect::<Vec<PathBuf>>();
+        let server = dirs::home_dir().unwrap().join(".vscode-server");
+        if server.is_dir() {
+            files.push(server.join("data/Machine/settings.json"));
+        }
+        files
+    }
+
+    /// The value of every managed key, `None` for the ones to remove.
+    fn generate_settings(setting: &ProxySettings) -> Vec<(&'static str, Option<Value>)> {
+        let socks_only = setting.socks_only();
+        let scheme = if socks_only { "socks5" } else { "http" };
+        let authorization = setting
+            .auth
+            .as_ref()
+            .filter(|auth| !auth.username.is_empty())
+            .map(|auth| json!(auth.basic()));
+        vec![
+            (
+                "http.proxy",
+                Some(json!(format!(
+                    "{}://{}:{}",
+                    scheme, setting.host, setting.port
+                ))),
+            ),
+            (
+                "http.noProxy",
+                (!setting.no_proxy.is_empty()).then(|| json!(setting.no_proxy)),
+            ),
+            ("http.proxyAuthorization", authorization),
+        ]
+    }
+
+    fn set_settings(content: &str, setting: &ProxySettings) -> Result<String, Box<dyn Error>> {
+        Self::generate_settings(setting).into_iter().try_fold(
+            content.to_string(),
+            |content, (key, value)| match value {
+                Some(value) => jsonc::set_value(&content, key, &value),
+                None => jsonc::remove_value(&content, key),
+            },
+        )
+    }
+
+    fn unset_settings(content: &str) -> Result<String, Box<dyn Error>> {
+        Self::KEYS
+            .iter()
+            .try_fold(content.to_string(), |content, key| {
+                jsonc::remove_value(&content, key)
+            })
+    }
+
+    fn parse_settings(content: &str) -> Option<ProxySettings> {
+        let settings = jsonc::parse(content).ok()?;
+        let proxy = settings.get("http.proxy")?.as_str()?;
+        let (scheme, address) = proxy.split_once("://").unwrap_or(("http", proxy));
+        let address = address.trim_end_matches('/');
+        let address = address
+            .rsplit_once('@')
+            .map_or(address, |(_, address)| address);
+        let (host, port) = address.rsplit_once(':')?;
+        let auth = settings
+            .get("http.proxyAuthorization")
+            .and_then(Value::as_str)
+            .and_then(ProxyAuth::from_basic);
+        Some(ProxySettings {
+            host: host.to_string(),
+            port: port.to_string(),
+            auth,
+            protocols: if scheme.starts_with("socks") {
+                vec![ProxyProtocol::Socks]
+            } else {
+                Vec::new()
+            },
+            no_proxy: settings
+                .get("http.noProxy")
+                .and_then(Value::as_array)
+                .map(|hosts| {
+                    hosts
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            ..Default::default()
+        })
+    }
+}
+
+impl ProxyTarget for VSCode {
+    fn name(&self) -> &'static str {
+        "vscode"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let proxy_settings = self
+            .settings_files
+            .iter()
+            .find_map(|path| Self::parse_settings(&fs::read_to_string(path).ok()?))?;
+        Some(vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        for path in &self.settings_files {
+            let content = fs::read_to_string(path).unwrap_or_default();
+            let updated = Self::set_settings(&content, setting)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, updated)?;
+        }
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        for path in &self.settings_files {
+            if let Ok(content) = fs::read_to_string(path) {
+                let updated = Self::unset_settings(&content)
+                    .map_err(|err| format!("{}: {}", path.display(), err))?;
+                if updated != content {
+                    fs::write(path, updated)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_json() {
+        let content = r#"{
+    // Keep the font readable
+    "editor.fontSize": 14,
+    "http.proxyStrictSSL": false,
+}
+"#;
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            auth: Some(ProxyAuth::new("alice".to_string(), "s3cret".to_string())),
+            no_proxy: vec!["localhost".to_string()],
+            ..Default::default()
+        };
+        let updated = VSCode::set_settings(content, &setting).unwrap();
+        assert_eq!(
+            updated,
+            r#"{
+    // Keep the font readable
+    "editor.fontSize": 14,
+    "http.proxyStrictSSL": false,
+    "http.proxy": "http://proxy.corp:3128",
+    "http.noProxy": [
+        "localhost"
+    ],
+    "http.proxyAuthorization": "Basic YWxpY2U6czNjcmV0"
+}
+"#
+        );
+
+        let parsed = VSCode::parse_settings(&updated).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.no_proxy, ["localhost"]);
+        assert_eq!(parsed.auth.unwrap().password, "s3cret");
+
+        let unset = VSCode::unset_settings(&updated).unwrap();
+        assert_eq!(
+            unset,
+            r#"{
+    // Keep the font readable
+    "editor.fontSize": 14,
+    "http.proxyStrictSSL": false
+}
+"#
+        );
+        assert!(VSCode::parse_settings(&unset).is_none());
+    }
+}
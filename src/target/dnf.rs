@@ -0,0 +1,316 @@
+use crate::{
+    proxy::{ProxyAuth, ProxySettings},
+    target::{ini, settings_from_url, ProxyTarget},
+};
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+const MAIN_KEYS: [&str; 4] = [
+    "proxy",
+    "proxy_username",
+    "proxy_password",
+    "proxy_auth_method",
+];
+
+/// The value dnf reads as "connect to this repository directly".
+const DIRECT: &str = "_none_";
+
+/// Marks the `proxy=_none_` overrides we write, on the line above them since
+/// dnf has no inline comments.
+const TAG: &str = "# auto-proxy";
+
+/// The `[main]` proxy options of `dnf.conf`, plus `proxy=_none_` for the
+/// repositories whose base URL is covered by `no_proxy`, which dnf does not
+/// honour on its own. Only overrides carrying our tag are ever removed.
+pub struct Dnf {
+    config_file: PathBuf,
+    repos_dir: PathBuf,
+}
+
+impl Dnf {
+    pub fn new(config_file: PathBuf, repos_dir: PathBuf) -> Self {
+        Self {
+            config_file,
+            repos_dir,
+        }
+    }
+
+    pub fn config_file() -> PathBuf {
+        PathBuf::from("/etc/dnf/dnf.conf")
+    }
+
+    pub fn repos_dir() -> PathBuf {
+        PathBuf::from("/etc/yum.repos.d")
+    }
+
+    fn set_main(content: &str, setting: &ProxySettings) -> String {
+        let mut content = ini::set_value(
+            content,
+            "main",
+            "proxy",
+            &format!("http://{}:{}", setting.host, setting.port),
+        );
+        match setting
+            .auth
+            .as_ref()
+            .filter(|auth| !auth.username.is_empty())
+        {
+            Some(auth) => {
+                content = ini::set_value(&content, "main", "proxy_username", &auth.username);
+                content = ini::set_value(&content, "main", "proxy_password", &auth.password);
+                content = ini::set_value(&content, "main", "proxy_auth_method", "basic");
+            }
+            None => {
+                for key in &MAIN_KEYS[1..] {
+                    content = ini::remove_value(&content, "main", key);
+                }
+            }
+        }
+        content
+    }
+
+    fn unset_main(content: &str) -> String {
+        MAIN_KEYS.iter().fold(content.to_string(), |content, key| {
+            ini::remove_value(&content, "main", key)
+        })
+    }
+
+    fn get_main(content: &str) -> Option<ProxySettings> {
+        let proxy = ini::get_value(content, "main", "proxy").filter(|proxy| proxy != DIRECT)?;
+        let mut proxy_settings = settings_from_url(&proxy, None)?;
+        if let Some(username) = ini::get_value(content, "main", "proxy_username") {
+            let password = ini::get_value(content, "main", "proxy_password").unwrap_or_default();
+            proxy_settings.auth = Some(ProxyAuth::new(username, password));
+        }
+        Some(proxy_settings)
+    }
+
+    fn url_host(url: &str) -> Option<&str> {
+        let (_, rest) = url.split_once("://")?;
+        let authority = rest.split('/').next()?;
+        let host = authority.rsplit('@').next()?;
+        host.split(':').next().filter(|host| !host.is_empty())
+    }
+
+    /// Matches `host` the way curl reads `no_proxy`: an entry covers the
+    /// domain itself and every subdomain, with or without a leading dot.
+    fn bypasses(host: &str, no_proxy: &[String]) -> bool {
+        no_proxy.iter().any(|entry| {
+            let entry = entry.trim();
+            let domain = entry.trim_start_matches("*.").trim_start_matches('.');
+            entry == "*"
+                || (!domain.is_empty()
+                    && (host == domain || host.ends_with(&format!(".{}", domain))))
+        })
+    }
+
+    /// Index of the `proxy` line in `section`, and whether our tag is on the
+    /// line above it.
+    fn proxy_line(lines: &[&str], section: &str) -> Option<(usize, bool)> {
+        let mut current = None;
+        for (index, line) in lines.iter().enumerate() {
+            let line = line.trim();
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                current = Some(name.trim());
+            } else if current == Some(section)
+                && line
+                    .split_once('=')
+                    .is_some_and(|(key, _)| key.trim() == "proxy")
+            {
+                return Some((index, index > 0 && lines[index - 1].trim() == TAG));
+            }
+        }
+        None
+    }
+
+    fn join(lines: &[&str]) -> String {
+        let mut content = lines.join("\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        content
+    }
+
+    fn add_override(content: &str, section: &str) -> String {
+        let content = ini::set_value(content, section, "proxy", DIRECT);
+        let mut lines = content.lines().collect::<Vec<&str>>();
+        if let Some((index, _)) = Self::proxy_line(&lines, section) {
+            lines.insert(index, TAG);
+        }
+        Self::join(&lines)
+    }
+
+    fn remove_override(content: &str, section: &str) -> String {
+        let mut lines = content.lines().collect::<Vec<&str>>();
+        match Self::proxy_line(&lines, section) {
+            Some((index, true)) => {
+                lines.drain(index - 1..=index);
+                Self::join(&lines)
+            }
+            _ => content.to_string(),
+        }
+    }
+
+    /// Adds a tagged `proxy=_none_` to the repositories with a bypassed base
+    /// URL and drops our tagged ones from the others. Repositories with a
+    /// `proxy` of their own are left alone.
+    fn set_repo_overrides(content: &str, no_proxy: &[String]) -> String {
+        let lines = content.lines().collect::<Vec<&str>>();
+        let mut updated = content.to_string();
+        for section in ini::sections(content) {
+            let bypassed = ini::get_value(content, section, "baseurl").is_some_and(|baseurl| {
+                baseurl
+                    .split([',', ' '])
+                    .filter_map(Self::url_host)
+                    .any(|host| Self::bypasses(host, no_proxy))
+            });
+            match Self::proxy_line(&lines, section) {
+                None if bypassed => updated = Self::add_override(&updated, section),
+                Some((_, true)) if !bypassed => updated = Self::remove_override(&updated, section),
+                _ => {}
+            }
+        }
+        updated
+    }
+
+    fn repo_files(&self) -> Vec<PathBuf> {
+        let mut files = fs::read_dir(&self.repos_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "repo"))
+                    .collect::<Vec<PathBuf>>()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+
+    fn update_repos(&self, no_proxy: &[String]) -> Result<(), Box<dyn Error>> {
+        for path in self.repo_files() {
+            let content = fs::read_to_string(&path)?;
+            let updated = Self::set_repo_overrides(&content, no_proxy);
+            if updated != content {
+                fs::write(&path, updated)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProxyTarget for Dnf {
+    fn name(&self) -> &'static str {
+        "dnf"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.config_file).ok()?;
+        Self::get_main(&content).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.config_file).unwrap_or_default();
+        if let Some(parent) = self.config_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.config_file, Self::set_main(&content, setting))?;
+        self.update_repos(&setting.no_proxy)
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.config_file) {
+            fs::write(&self.config_file, Self::unset_main(&content))?;
+        }
+        self.update_repos(&[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_main_section_is_edited_in_place() {
+        let content = "# see `man dnf.conf`\n\
+                       [main]\n\
+                       gpgcheck=1\n\
+                       installonly_limit=3\n\
+                       clean_requirements_on_remove=True\n";
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            auth: Some(ProxyAuth::new("jane".to_string(), "secret".to_string())),
+            ..Default::default()
+        };
+
+        let updated = Dnf::set_main(content, &setting);
+        assert_eq!(
+            updated,
+            "# see `man dnf.conf`\n\
+             [main]\n\
+             gpgcheck=1\n\
+             installonly_limit=3\n\
+             clean_requirements_on_remove=True\n\
+             proxy = http://proxy.corp:3128\n\
+             proxy_username = jane\n\
+             proxy_password = secret\n\
+             proxy_auth_method = basic\n"
+        );
+
+        let parsed = Dnf::get_main(&updated).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.auth.unwrap().username, "jane");
+
+        assert_eq!(Dnf::unset_main(&updated), content);
+    }
+
+    #[test]
+    fn test_repo_overrides() {
+        let content = "[internal]\n\
+                       name=Internal\n\
+                       baseurl=https://mirror.corp/fedora/$releasever/\n\
+                       \n\
+                       [fedora]\n\
+                       name=Fedora\n\
+                       metalink=https://mirrors.fedoraproject.org/metalink\n";
+        let no_proxy = vec!["localhost".to_string(), ".corp".to_string()];
+
+        let updated = Dnf::set_repo_overrides(content, &no_proxy);
+        assert_eq!(
+            updated,
+            "[internal]\n\
+             name=Internal\n\
+             baseurl=https://mirror.corp/fedora/$releasever/\n\
+             # auto-proxy\n\
+             proxy = _none_\n\
+             \n\
+             [fedora]\n\
+             name=Fedora\n\
+             metalink=https://mirrors.fedoraproject.org/metalink\n"
+        );
+        assert_eq!(Dnf::set_repo_overrides(&updated, &no_proxy), updated);
+        assert_eq!(Dnf::set_repo_overrides(&updated, &[]), content);
+    }
+
+    #[test]
+    fn test_user_override_survives_unset() {
+        let content = "[vpn-only]\n\
+                       name=VPN only\n\
+                       baseurl=https://packages.corp/el9/\n\
+                       proxy=_none_\n";
+        let no_proxy = vec![".corp".to_string()];
+
+        assert_eq!(Dnf::set_repo_overrides(content, &no_proxy), content);
+        assert_eq!(Dnf::set_repo_overrides(content, &[]), content);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    proxy::ProxySettings,
+    target::{parse_json_object, proxy_url, settings_from_url, ProxyTarget},
+};
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+/// The `proxies.default` block of the Docker CLI configuration, which the
+/// client injects into every build and container it starts.
+pub struct DockerClient {
+    config_file: PathBuf,
+}
+
+impl DockerClient {
+    pub fn new(config_file: PathBuf) -> Self {
+        Self { config_file }
+    }
+
+    pub fn config_file() -> PathBuf {
+        match env::var_os("DOCKER_CONFIG") {
+            Some(config_dir) => PathBuf::from(config_dir).join("config.json"),
+            None => dirs::home_dir().unwrap().join(".docker/config.json"),
+        }
+    }
+
+    /// Sets `proxies.default`, keeping `auths`, `credHelpers` and every other
+    /// key as it is.
+    fn set_default_proxies(
+        content: &str,
+        setting: &ProxySettings,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut config = parse_json_object(content)?;
+        let proxy = proxy_url(setting);
+        let mut default = json!({
+            "httpProxy": proxy,
+            "httpsProxy": proxy,
+        });
+        if !setting.no_proxy.is_empty() {
+            default["noProxy"] = Value::from(setting.no_proxy.join(","));
+        }
+
+        let proxies = config
+            .entry("proxies")
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .ok_or("proxies is not a JSON object")?;
+        proxies.insert("default".to_string(), default);
+
+        Ok(serde_json::to_string_pretty(&config)? + "\n")
+    }
+
+    /// Removes `proxies.default`, and `proxies` along with it once empty.
+    fn unset_default_proxies(content: &str) -> Result<String, Box<dyn Error>> {
+        let mut config = parse_json_object(content)?;
+        if let Some(proxies) = config.get_mut("proxies").and_then(Value::as_object_mut) {
+            proxies.remove("default");
+            if proxies.is_empty() {
+                config.remove("proxies");
+            }
+        }
+
+        Ok(serde_json::to_string_pretty(&config)? + "\n")
+    }
+
+    fn get_default_proxies(content: &str) -> Option<ProxySettings> {
+        let config = parse_json_object(content).ok()?;
+        let default = config.get("proxies")?.get("default")?;
+        let proxy = default
+            .get("httpsProxy")
+            .or_else(|| default.get("httpProxy"))?
+            .as_str()?;
+        settings_from_url(proxy, default.get("noProxy").and_then(Value::as_str))
+    }
+}
+
+impl ProxyTarget for DockerClient {
+    fn name(&self) -> &'static str {
+        "docker-client"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.config_file).ok()?;
+        Self::get_default_proxies(&content).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.config_file).unwrap_or_default();
+        if let Some(parent) = self.config_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            &self.config_file,
+            Self::set_default_proxies(&content, setting)?,
+        )?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.config_file) {
+            fs::write(&self.config_file, Self::unset_default_proxies(&content)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_proxies() {
+        let content = r#"{
+  "auths": {
+    "registry.corp": {
+      "auth": "amFuZTpzZWNyZXQ="
+    }
+  },
+  "credHelpers": {
+    "gcr.io": "gcloud"
+  }
+}
+"#;
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            no_proxy: vec!["localhost".to_string(), "registry.corp".to_string()],
+            ..Default::default()
+        };
+
+        let updated = DockerClient::set_default_proxies(content, &setting).unwrap();
+        let config: Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(config["auths"]["registry.corp"]["auth"], "amFuZTpzZWNyZXQ=");
+        assert_eq!(config["credHelpers"]["gcr.io"], "gcloud");
+        assert_eq!(
+            config["proxies"]["default"]["httpProxy"],
+            "http://proxy.corp:3128"
+        );
+
+        let parsed = DockerClient::get_default_proxies(&updated).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.port, "3128");
+        assert_eq!(parsed.no_proxy, ["localhost", "registry.corp"]);
+
+        assert_eq!(
+            DockerClient::unset_default_proxies(&updated).unwrap(),
+            content
+        );
+    }
+}
@@ -0,0 +1,443 @@
+use crate::{
+    proxy::{ProxyProtocol, ProxySettings},
+    target::{ini, managed_block, parse_json_object, ProxyTarget},
+};
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Map, Value};
+
+/// Firefox's `network.proxy.type` values.
+const TYPE_MANUAL: i64 = 1;
+const TYPE_PAC: i64 = 2;
+const TYPE_SYSTEM: i64 = 5;
+
+/// Where Firefox reads its proxy from.
+#[derive(Debug, PartialEq)]
+pub enum FirefoxPrefs {
+    /// A managed block in the `user.js` of every profile listed in this
+    /// `profiles.ini`.
+    UserJs(PathBuf),
+    /// The `Proxy` policy of a system-wide `policies.json`, for managed
+    /// machines.
+    Policies(PathBuf),
+}
+
+pub struct Firefox {
+    prefs: FirefoxPrefs,
+}
+
+impl Firefox {
+    pub fn new(prefs: FirefoxPrefs) -> Self {
+        Self { prefs }
+    }
+
+    pub fn profiles_ini() -> PathBuf {
+        dirs::home_dir()
+            .unwrap()
+            .join(".mozilla/firefox/profiles.ini")
+    }
+
+    pub fn policies_file() -> PathBuf {
+        PathBuf::from("/etc/firefox/policies/policies.json")
+    }
+
+    /// The profile directories listed in `profiles.ini`.
+    fn profile_dirs(profiles_ini: &Path) -> Vec<PathBuf> {
+        let content = match fs::read_to_string(profiles_ini) {
+            Ok(content) => content,
+            Err(_) => return Vec::new(),
+        };
+        let base = profiles_ini.parent().unwrap_or(Path::new(""));
+
+        ini::sections(&content)
+            .into_iter()
+            .filter(|section| section.starts_with("Profile"))
+            .filter_map(|section| {
+                let path = ini::get_value(&content, section, "Path")?;
+                let relative = ini::get_value(&content, section, "IsRelative")
+                    .is_none_or(|relative| relative == "1");
+                Some(if relative {
+                    base.join(path)
+                } else {
+                    PathBuf::from(path)
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the settings name a SOCKS proxy, and an HTTP one.
+    fn proxy_kinds(setting: &ProxySettings) -> (bool, bool) {
+        let socks = setting.protocols.contains(&ProxyProtocol::Socks);
+        let http = setting.protocols.is_empty()
+            || setting
+                .protocols
+                .iter()
+                .any(|protocol| *protocol != ProxyProtocol::Socks);
+        (socks, http)
+    }
+
+    fn generate_prefs(setting: &ProxySettings) -> Vec<(&'static str, Value)> {
+        if let Some(pac_url) = &setting.pac_url {
+            return vec![
+                ("network.proxy.type", json!(TYPE_PAC)),
+                ("network.proxy.autoconfig_url", json!(pac_url)),
+            ];
+        }
+
+        let (socks, http) = Self::proxy_kinds(setting);
+        let port = setting
+            .port
+            .parse::<u16>()
+            .map_or(json!(0), |port| json!(port));
+        let mut prefs = vec![("network.proxy.type", json!(TYPE_MANUAL))];
+        if http {
+            prefs.push(("network.proxy.http", json!(setting.host)));
+            prefs.push(("network.proxy.http_port", port.clone()));
+            prefs.push(("network.proxy.ssl", json!(setting.host)));
+            prefs.push(("network.proxy.ssl_port", port.clone()));
+        }
+        if socks {
+            prefs.push(("network.proxy.socks", json!(setting.host)));
+            prefs.push(("network.proxy.socks_port", port));
+            prefs.push(("network.proxy.socks_version", json!(5)));
+            prefs.push(("network.proxy.socks_remote_dns", json!(true)));
+        }
+        prefs.push((
+            "network.proxy.no_proxies_on",
+            json!(setting.no_proxy.join(", ")),
+        ));
+        prefs
+    }
+
+    fn generate_user_js(prefs: &[(&str, Value)]) -> String {
+        prefs
+            .iter()
+            .map(|(name, value)| format!("user_pref(\"{}\", {});\n", name, value))
+            .collect()
+    }
+
+    fn parse_user_js(content: &str) -> Option<ProxySettings> {
+        let prefs = content
+            .lines()
+            .filter_map(|line| {
+                let arguments = line.trim().strip_prefix("user_pref(")?.strip_suffix(");")?;
+                let (name, value) = arguments.split_once(',')?;
+                let name = serde_json::from_str::<String>(name.trim()).ok()?;
+                let value = serde_json::from_str::<Value>(value.trim()).ok()?;
+                Some((name, value))
+            })
+            .collect::<Map<String, Value>>();
+        let pref = |name: &str| prefs.get(&format!("network.proxy.{}", name));
+
+        match pref("type")?.as_i64()? {
+            TYPE_PAC => Some(ProxySettings {
+                pac_url: Some(pref("autoconfig_url")?.as_str()?.to_string()),
+                ..Default::default()
+            }),
+            TYPE_MANUAL => {
+                let (host, port, protocols) = match pref("http") {
+                    Some(host) => (host, pref("http_port")?, Vec::new()),
+                    None => (
+                        pref("socks")?,
+                        pref("socks_port")?,
+                        vec![ProxyProtocol::Socks],
+                    ),
+                };
+                Some(ProxySettings {
+                    host: host.as_str()?.to_string(),
+                    port: port.to_string(),
+                    protocols,
+                    no_proxy: pref("no_proxies_on")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|host| !host.is_empty())
+                        .map(String::from)
+                        .collect(),
+                    ..Default::default()
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The `Proxy` policy, see https://mozilla.github.io/policy-templates/#proxy.
+    fn generate_policy(setting: &ProxySettings) -> Value {
+        if let Some(pac_url) = &setting.pac_url {
+            return json!({ "Mode": "autoConfig", "AutoConfigURL": pac_url });
+        }
+
+        let (socks, http) = Self::proxy_kinds(setting);
+        let address = format!("{}:{}", setting.host, setting.port);
+        let mut policy = json!({ "Mode": "manual" });
+        if http {
+            policy["HTTPProxy"] = json!(address);
+            policy["UseHTTPProxyForAllProtocols"] = json!(true);
+        }
+        if socks {
+            policy["SOCKSProxy"] = json!(address);
+            policy["SOCKSVersion"] = json!(5);
+            policy["UseProxyForDNS"] = json!(true);
+        }
+        policy["Passthrough"] = json!(setting.no_proxy.join(", "));
+        policy
+    }
+
+    fn set_policy(content: &str, setting: &ProxySettings) -> Result<String, Box<dyn Error>> {
+        let mut policies_json = parse_json_object(content)?;
+        let policies = policies_json
+            .entry("policies")
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .ok_or("\"policies\" is not an object")?;
+        policies.insert("Proxy".to_string(), Self::generate_policy(setting));
+        Ok(serde_json::to_string_pretty(&policies_json)? + "\n")
+    }
+
+    fn unset_policy(content: &str) -> Result<String, Box<dyn Error>> {
+        let mut policies_json = parse_json_object(content)?;
+        if let Some(policies) = policies_json
+            .get_mut("policies")
+            .and_then(Value::as_object_mut)
+        {
+            policies.remove("Proxy");
+            if policies.is_empty() {
+                policies_json.remove("policies");
+            }
+        }
+        Ok(serde_json::to_string_pretty(&policies_json)? + "\n")
+    }
+
+    fn get_policy(content: &str) -> Option<ProxySettings> {
+        let policy = parse_json_object(content)
+            .ok()?
+            .get("policies")?
+            .get("Proxy")?
+            .clone();
+        if policy["Mode"] == "autoConfig" {
+            return Some(ProxySettings {
+                pac_url: Some(policy["AutoConfigURL"].as_str()?.to_string()),
+                ..Default::default()
+            });
+        }
+
+        let (address, protocols) = match policy["HTTPProxy"].as_str() {
+            Some(address) => (address, Vec::new()),
+            None => (policy["SOCKSProxy"].as_str()?, vec![ProxyProtocol::Socks]),
+        };
+        let (host, port) = address.rsplit_once(':')?;
+        Some(ProxySettings {
+            host: host.to_string(),
+            port: port.to_string(),
+            protocols,
+            no_proxy: policy["Passthrough"]
+                .as_str()
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .map(String::from)
+                .collect(),
+            ..Default::default()
+        })
+    }
+}
+
+impl ProxyTarget for Firefox {
+    fn name(&self) -> &'static str {
+        "firefox"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        match &self.prefs {
+            FirefoxPrefs::UserJs(profiles_ini) => {
+                let proxy_settings =
+                    Self::profile_dirs(profiles_ini)
+                        .into_iter()
+                        .find_map(|profile_dir| {
+                            let content = fs::read_to_string(profile_dir.join("user.js")).ok()?;
+                            Self::parse_user_js(managed_block::get(&content, "//")?)
+                        })?;
+                Some(vec![proxy_settings])
+            }
+            FirefoxPrefs::Policies(path) => {
+                let content = fs::read_to_string(path).ok()?;
+                Self::get_policy(&content).map(|proxy_settings| vec![proxy_settings])
+            }
+        }
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        match &self.prefs {
+            FirefoxPrefs::UserJs(profiles_ini) => {
+                let block = Self::generate_user_js(&Self::generate_prefs(setting));
+                for profile_dir in Self::profile_dirs(profiles_ini) {
+                    let user_js = profile_dir.join("user.js");
+                    let content = fs::read_to_string(&user_js).unwrap_or_default();
+                    fs::write(&user_js, managed_block::replace(&content, "//", &block))?;
+                }
+            }
+            FirefoxPrefs::Policies(path) => {
+                let content = fs::read_to_string(path).unwrap_or_default();
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, Self::set_policy(&content, setting)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Firefox copies `user.js` into `prefs.js` on every start, so removing
+    /// the block would leave the last proxy behind; it is replaced with one
+    /// switching back to the system proxy instead.
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        match &self.prefs {
+            FirefoxPrefs::UserJs(profiles_ini) => {
+                let block = Self::generate_user_js(&[("network.proxy.type", json!(TYPE_SYSTEM))]);
+                for profile_dir in Self::profile_dirs(profiles_ini) {
+                    let user_js = profile_dir.join("user.js");
+                    if let Ok(content) = fs::read_to_string(&user_js) {
+                        if managed_block::get(&content, "//").is_some() {
+                            fs::write(&user_js, managed_block::replace(&content, "//", &block))?;
+                        }
+                    }
+                }
+            }
+            FirefoxPrefs::Policies(path) => {
+                if let Ok(content) = fs::read_to_string(path) {
+                    fs::write(path, Self::unset_policy(&content)?)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn supports_pac(&self) -> bool {
+        true
+    }
+
+    fn follow_up(&self) -> Option<String> {
+        Some("Restart Firefox to pick up the new proxy".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn office_settings() -> ProxySettings {
+        ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            protocols: vec![ProxyProtocol::Http, ProxyProtocol::Socks],
+            no_proxy: vec!["localhost".to_string(), ".corp".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_user_js() {
+        let block = Firefox::generate_user_js(&Firefox::generate_prefs(&office_settings()));
+        assert_eq!(
+            block,
+            "user_pref(\"network.proxy.type\", 1);\n\
+             user_pref(\"network.proxy.http\", \"proxy.corp\");\n\
+             user_pref(\"network.proxy.http_port\", 3128);\n\
+             user_pref(\"network.proxy.ssl\", \"proxy.corp\");\n\
+             user_pref(\"network.proxy.ssl_port\", 3128);\n\
+             user_pref(\"network.proxy.socks\", \"proxy.corp\");\n\
+             user_pref(\"network.proxy.socks_port\", 3128);\n\
+             user_pref(\"network.proxy.socks_version\", 5);\n\
+             user_pref(\"network.proxy.socks_remote_dns\", true);\n\
+             user_pref(\"network.proxy.no_proxies_on\", \"localhost, .corp\");\n"
+        );
+
+        let parsed = Firefox::parse_user_js(&block).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.port, "3128");
+        assert_eq!(parsed.no_proxy, ["localhost", ".corp"]);
+
+        let pac = ProxySettings {
+            pac_url: Some("http://wpad.corp/proxy.pac".to_string()),
+            ..Default::default()
+        };
+        let block = Firefox::generate_user_js(&Firefox::generate_prefs(&pac));
+        assert_eq!(
+            Firefox::parse_user_js(&block).unwrap().pac_url.as_deref(),
+            Some("http://wpad.corp/proxy.pac")
+        );
+    }
+
+    #[test]
+    fn test_profile_dirs() {
+        let dir = std::env::temp_dir().join(format!("auto-proxy-firefox-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let profiles_ini = dir.join("profiles.ini");
+        fs::write(
+            &profiles_ini,
+            "[Install4F96D1932A9F858E]\n\
+             Default=abcd.default-release\n\
+             \n\
+             [Profile0]\n\
+             Name=default-release\n\
+             IsRelative=1\n\
+             Path=abcd.default-release\n\
+             \n\
+             [Profile1]\n\
+             Name=work\n\
+             IsRelative=0\n\
+             Path=/data/firefox/work\n\
+             \n\
+             [General]\n\
+             Version=2\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Firefox::profile_dirs(&profiles_ini),
+            [
+                dir.join("abcd.default-release"),
+                PathBuf::from("/data/firefox/work")
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_policies_json() {
+        let content = r#"{"policies": {"DisableTelemetry": true}}"#;
+
+        let updated = Firefox::set_policy(content, &office_settings()).unwrap();
+        let policies: Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(
+            policies["policies"]["Proxy"],
+            json!({
+                "Mode": "manual",
+                "HTTPProxy": "proxy.corp:3128",
+                "UseHTTPProxyForAllProtocols": true,
+                "SOCKSProxy": "proxy.corp:3128",
+                "SOCKSVersion": 5,
+                "UseProxyForDNS": true,
+                "Passthrough": "localhost, .corp"
+            })
+        );
+        assert_eq!(policies["policies"]["DisableTelemetry"], true);
+        assert_eq!(Firefox::get_policy(&updated).unwrap().host, "proxy.corp");
+
+        let removed = Firefox::unset_policy(&updated).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&removed).unwrap(),
+            json!({"policies": {"DisableTelemetry": true}})
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{proxy::ProxySettings, target::ProxyTarget};
+
+use std::error::Error;
+
+/// Flatpak apps have no proxy setting of their own: they ask the desktop
+/// portal, whose GNOME backend serves `org.gnome.system.proxy`. This target
+/// changes nothing and only tells the user whether that will work.
+pub struct Flatpak {
+    desktop_environment: String,
+}
+
+impl Flatpak {
+    pub fn new(desktop_environment: &str) -> Self {
+        Self {
+            desktop_environment: desktop_environment.to_string(),
+        }
+    }
+
+    fn guidance(desktop_environment: &str) -> String {
+        if desktop_environment
+            .split(':')
+            .any(|desktop| desktop == "gnome")
+        {
+            "Flatpak apps read the GNOME proxy settings through the settings portal; \
+             restart running apps to pick up the change"
+                .to_string()
+        } else {
+            "Flatpak apps only see the proxy when the portal serves \
+             org.gnome.system.proxy; without GNOME set it with `gsettings`, or per app \
+             with `flatpak override --user --env=http_proxy=... <app>`"
+                .to_string()
+        }
+    }
+}
+
+impl ProxyTarget for Flatpak {
+    fn name(&self) -> &'static str {
+        "flatpak"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        None
+    }
+
+    fn set(&self, _settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn follow_up(&self) -> Option<String> {
+        Some(Self::guidance(&self.desktop_environment))
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{
+    proxy::ProxySettings,
+    setup::shell_quote,
+    target::{proxy_url, settings_from_url, ProxyTarget},
+};
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Git's proxy, written with `git config` so that includes, comments and the
+/// rest of the file are left to git itself.
+///
+/// Hosts in `no_proxy` become `http.<url>.proxy` entries with an empty value,
+/// which turns the proxy off for them. A sibling `http.<url>.autoProxy` marks
+/// the ones we wrote, and only those are ever removed.
+/// `git://` remotes go through `core.gitProxy`. Git runs it as a single
+/// program with the host and port as arguments, so it points at a wrapper
+/// script handing those to `auto-proxy connect`.
+pub struct Git {
+    /// `None` for the user's global configuration.
+    config_file: Option<PathBuf>,
+    proxy_command: PathBuf,
+    helper: PathBuf,
+}
+
+impl Git {
+    pub fn new(config_file: Option<PathBuf>, proxy_command: PathBuf) -> Self {
+        Self {
+            config_file,
+            proxy_command,
+            helper: env::current_exe().unwrap_or_else(|_| PathBuf::from("auto-proxy")),
+        }
+    }
+
+    pub fn proxy_command() -> PathBuf {
+        dirs::home_dir().unwrap().join(".auto-proxy/git-proxy")
+    }
+
+    fn git_config(&self, args: &[&str]) -> Result<std::process::Output, Box<dyn Error>> {
+        let mut command = Command::new("git");
+        command.arg("config");
+        match &self.config_file {
+            Some(path) => command.arg("--file").arg(path),
+            None => command.arg("--global"),
+        };
+        Ok(command.args(args).output()?)
+    }
+
+    /// Runs `git config`, treating exit code 1 (key not found) and 5 (nothing
+    /// to unset) as an empty result.
+    fn run(&self, args: &[&str]) -> Result<String, Box<dyn Error>> {
+        let output = self.git_config(args)?;
+        match output.status.code() {
+            Some(0) => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+            Some(1) | Some(5) => Ok(String::new()),
+            _ => Err(format!(
+                "git config {}: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into()),
+        }
+    }
+
+    /// `(key, value)` of every entry matching `pattern`.
+    fn entries(&self, pattern: &str) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        Ok(self
+            .run(&["--null", "--get-regexp", pattern])?
+            .split('\0')
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| match entry.split_once('\n') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None => (entry.to_string(), String::new()),
+            })
+            .collect())
+    }
+
+    /// URL patterns of the `http.<url>.*` sections that bypass the proxy.
+    fn url_patterns(no_proxy: &[String]) -> Vec<String> {
+        no_proxy
+            .iter()
+            .map(|host| host.trim())
+            .filter(|host| !host.is_empty() && *host != "*" && !host.contains('/'))
+            .map(|host| match host.strip_prefix('.') {
+                Some(domain) => format!("*.{}", domain),
+                None => host.to_string(),
+            })
+            .flat_map(|host| ["http", "https"].map(|scheme| format!("{}://{}", scheme, host)))
+            .collect()
+    }
+
+    fn no_proxy_from_patterns(urls: &[String]) -> Vec<String> {
+        let mut no_proxy = Vec::new();
+        for url in urls {
+            let host = url.split_once("://").map_or(url.as_str(), |(_, host)| host);
+            let host = match host.strip_prefix("*.") {
+                Some(domain) => format!(".{}", domain),
+                None => host.to_string(),
+            };
+            if !no_proxy.contains(&host) {
+                no_proxy.push(host);
+            }
+        }
+        no_proxy
+    }
+
+    fn generate_proxy_command(&self) -> String {
+        format!(
+            "#!/bin/sh\n# Managed by auto-proxy\nexec {} connect \"$1\" \"$2\"\n",
+            shell_quote(&self.helper.display().to_string())
+        )
+    }
+
+    fn set_git_proxy(&self) -> Result<(), Box<dyn Error>> {
+        let proxy_command = self.proxy_command.display().to_string();
+        let git_proxy = self.run(&["--get", "core.gitProxy"])?;
+        if !git_proxy.trim().is_empty() && git_proxy.trim() != proxy_command {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.proxy_command.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.proxy_command, self.generate_proxy_command())?;
+        fs::set_permissions(&self.proxy_command, fs::Permissions::from_mode(0o755))?;
+        self.run(&["core.gitProxy", &proxy_command])?;
+        Ok(())
+    }
+
+    /// The URLs of the bypass entries carrying our marker.
+    fn bypass_urls(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self
+            .entries(r"^http\..+\.autoproxy$")?
+            .into_iter()
+            .filter_map(|(key, _)| {
+                let url = key.strip_prefix("http.")?.strip_suffix(".autoproxy")?;
+                Some(url.to_string())
+            })
+            .collect())
+    }
+
+    /// Whether the user set a proxy of their own for `url`.
+    fn has_own_proxy(&self, url: &str) -> Result<bool, Box<dyn Error>> {
+        let pattern = format!("^{}$", regex::escape(&format!("http.{}.proxy", url)));
+        Ok(!self.entries(&pattern)?.is_empty())
+    }
+
+    fn add_bypass(&self, url: &str) -> Result<(), Box<dyn Error>> {
+        self.run(&[&format!("http.{}.proxy", url), ""])?;
+        self.run(&[&format!("http.{}.autoProxy", url), "true"])?;
+        Ok(())
+    }
+
+    fn remove_bypass(&self, url: &str) -> Result<(), Box<dyn Error>> {
+        let section = format!("http.{}", url);
+        self.run(&["--unset-all", &format!("{}.proxy", section)])?;
+        self.run(&["--unset-all", &format!("{}.autoProxy", section)])?;
+        let pattern = format!("^{}\\.", regex::escape(&section));
+        if self.entries(&pattern)?.is_empty() {
+            // Recent git drops the emptied section by itself, so this may
+            // find nothing to remove.
+            self.git_config(&["--remove-section", &section])?;
+        }
+        Ok(())
+    }
+
+    fn unset_git_proxy(&self) -> Result<(), Box<dyn Error>> {
+        let git_proxy = self.run(&["--get", "core.gitProxy"])?;
+        if git_proxy.trim() == self.proxy_command.display().to_string() {
+            self.run(&["--unset-all", "core.gitProxy"])?;
+            fs::remove_file(&self.proxy_command)?;
+        }
+        Ok(())
+    }
+}
+
+impl ProxyTarget for Git {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let proxy = self.run(&["--get", "http.proxy"]).ok()?;
+        let mut proxy_settings = settings_from_url(proxy.trim(), None)?;
+        proxy_settings.no_proxy = Self::no_proxy_from_patterns(&self.bypass_urls().ok()?);
+        Some(vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        self.run(&["http.proxy", &proxy_url(setting)])?;
+
+        let urls = Self::url_patterns(&setting.no_proxy);
+        let ours = self.bypass_urls()?;
+        for url in &ours {
+            if !urls.contains(url) {
+                self.remove_bypass(url)?;
+            }
+        }
+        for url in &urls {
+            if !ours.contains(url) && !self.has_own_proxy(url)? {
+                self.add_bypass(url)?;
+            }
+        }
+
+        self.set_git_proxy()
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        self.run(&["--unset-all", "http.proxy"])?;
+        for url in self.bypass_urls()? {
+            self.remove_bypass(&url)?;
+        }
+        self.unset_git_proxy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target::command_exists;
+
+    #[test]
+    fn test_url_patterns() {
+        let no_proxy = vec![
+            "gitlab.corp".to_string(),
+            ".internal".to_string(),
+            "10.0.0.0/8".to_string(),
+        ];
+        let urls = Git::url_patterns(&no_proxy);
+        assert_eq!(
+            urls,
+            [
+                "http://gitlab.corp",
+                "https://gitlab.corp",
+                "http://*.internal",
+                "https://*.internal",
+            ]
+        );
+        assert_eq!(
+            Git::no_proxy_from_patterns(&urls),
+            ["gitlab.corp", ".internal"]
+        );
+    }
+
+    #[test]
+    fn test_git_config() {
+        if !command_exists("git") {
+            return;
+        }
+        let dir = env::temp_dir().join(format!("auto-proxy-gitconfig-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+        fs::write(
+            &path,
+            "[user]\n\tname = Alice\n[http \"https://gitlab.corp\"]\n\tsslVerify = false\n\
+             [http \"https://vpn.corp\"]\n\tproxy =\n",
+        )
+        .unwrap();
+        let git = Git::new(Some(path.clone()), dir.join("git-proxy"));
+
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            no_proxy: vec!["gitlab.corp".to_string(), "vpn.corp".to_string()],
+            ..Default::default()
+        };
+        git.set(vec![&setting]).unwrap();
+        let proxy_settings = git.get().unwrap();
+        assert_eq!(proxy_settings[0].host, "proxy.corp");
+        assert_eq!(proxy_settings[0].no_proxy, ["gitlab.corp", "vpn.corp"]);
+
+        git.unset().unwrap();
+        assert!(git.get().is_none());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[user]\n\tname = Alice\n[http \"https://gitlab.corp\"]\n\tsslVerify = false\n\
+             [http \"https://vpn.corp\"]\n\tproxy =\n"
+        );
+        assert!(!dir.join("git-proxy").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_git_proxy_command() {
+        if !command_exists("git") {
+            return;
+        }
+        let dir = env::temp_dir().join(format!("auto-proxy-gitproxy-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // Stands in for `auto-proxy`, recording how the wrapper calls it.
+        let helper = dir.join("fake helper");
+        let calls = dir.join("calls");
+        fs::write(
+            &helper,
+            format!("#!/bin/sh\necho \"$@\" > {}\n", calls.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&helper, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = dir.join("gitconfig");
+        let mut git = Git::new(Some(config.clone()), dir.join("git-proxy"));
+        git.helper = helper;
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            ..Default::default()
+        };
+        git.set(vec![&setting]).unwrap();
+
+        let _ = Command::new("git")
+            .args(["ls-remote", "git://example.invalid/repo.git"])
+            .env("GIT_CONFIG_GLOBAL", &config)
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .output()
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&calls).unwrap(),
+            "connect example.invalid 9418\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{
+    proxy::{ProxyAuth, ProxyProtocol, ProxySettings},
+    target::{java_non_proxy_hosts, no_proxy_from_java, properties, ProxyTarget},
+};
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HTTP_KEYS: [&str; 5] = [
+    "proxyHost",
+    "proxyPort",
+    "proxyUser",
+    "proxyPassword",
+    "nonProxyHosts",
+];
+
+/// Proxy system properties in a `gradle.properties`, either the user's or
+/// one in a project directory.
+pub struct Gradle {
+    name: &'static str,
+    properties_file: PathBuf,
+}
+
+impl Gradle {
+    pub fn new(properties_file: PathBuf) -> Self {
+        Self {
+            name: "gradle",
+            properties_file,
+        }
+    }
+
+    pub fn for_project(project_dir: &Path) -> Self {
+        Self {
+            name: "gradle-project",
+            properties_file: project_dir.join("gradle.properties"),
+        }
+    }
+
+    pub fn properties_file() -> PathBuf {
+        let gradle_home = match env::var_os("GRADLE_USER_HOME") {
+            Some(gradle_home) => PathBuf::from(gradle_home),
+            None => dirs::home_dir().unwrap().join(".gradle"),
+        };
+        gradle_home.join("gradle.properties")
+    }
+
+    fn managed_keys() -> Vec<String> {
+        let mut keys = Vec::new();
+        for scheme in ["http", "https"] {
+            for key in HTTP_KEYS {
+                keys.push(format!("systemProp.{}.{}", scheme, key));
+            }
+        }
+        keys.push("systemProp.socksProxyHost".to_string());
+        keys.push("systemProp.socksProxyPort".to_string());
+        keys
+    }
+
+    fn generate_gradle_proxy_entries(setting: &ProxySettings) -> Vec<(String, String)> {
+        let socks = setting.protocols.contains(&ProxyProtocol::Socks);
+        let http = setting.protocols.is_empty()
+            || setting
+                .protocols
+                .iter()
+                .any(|protocol| *protocol != ProxyProtocol::Socks);
+
+        let mut entries = Vec::new();
+        if http {
+            for scheme in ["http", "https"] {
+                let key = |name: &str| format!("systemProp.{}.{}", scheme, name);
+                entries.push((key("proxyHost"), setting.host.clone()));
+                entries.push((key("proxyPort"), setting.port.clone()));
+                if let Some(auth) = setting
+                    .auth
+                    .as_ref()
+                    .filter(|auth| !auth.username.is_empty())
+                {
+                    entries.push((key("proxyUser"), auth.username.clone()));
+                    entries.push((key("proxyPassword"), auth.password.clone()));
+                }
+                // Java reads nonProxyHosts from the http properties only, but
+                // Gradle documents setting both.
+                if !setting.no_proxy.is_empty() {
+                    entries.push((
+                        key("nonProxyHosts"),
+                        java_non_proxy_hosts(&setting.no_proxy),
+                    ));
+                }
+            }
+        }
+        if socks {
+            entries.push((
+                "systemProp.socksProxyHost".to_string(),
+                setting.host.clone(),
+            ));
+            entries.push((
+                "systemProp.socksProxyPort".to_string(),
+                setting.port.clone(),
+            ));
+        }
+        entries
+    }
+
+    fn set_properties(content: &str, setting: &ProxySettings) -> String {
+        let keys = Self::managed_keys();
+        let keys = keys.iter().map(String::as_str).collect::<Vec<&str>>();
+        let entries = Self::generate_gradle_proxy_entries(setting);
+        let entries = entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.clone()))
+            .collect::<Vec<(&str, String)>>();
+        properties::replace_keys(content, &keys, &entries)
+    }
+
+    fn unset_properties(content: &str) -> String {
+        let keys = Self::managed_keys();
+        let keys = keys.iter().map(String::as_str).collect::<Vec<&str>>();
+        properties::remove_keys(content, &keys)
+    }
+
+    fn get_properties(content: &str) -> Option<ProxySettings> {
+        let value = |key: &str| properties::get_value(content, key).map(String::from);
+
+        let (host, port, protocols) = match (
+            value("systemProp.https.proxyHost").or_else(|| value("systemProp.http.proxyHost")),
+            value("systemProp.socksProxyHost"),
+        ) {
+            (Some(host), socks_host) => {
+                let port = value("systemProp.https.proxyPort")
+                    .or_else(|| value("systemProp.http.proxyPort"))?;
+                let mut protocols = vec![ProxyProtocol::Http, ProxyProtocol::Https];
+                if socks_host.is_some() {
+                    protocols.push(ProxyProtocol::Socks);
+                }
+                (host, port, protocols)
+            }
+            (None, Some(host)) => (
+                host,
+                value("systemProp.socksProxyPort")?,
+                vec![ProxyProtocol::Socks],
+            ),
+            (None, None) => return None,
+        };
+
+        let auth = value("systemProp.https.proxyUser")
+            .or_else(|| value("systemProp.http.proxyUser"))
+            .map(|username| {
+                let password = value("systemProp.https.proxyPassword")
+                    .or_else(|| value("systemProp.http.proxyPassword"))
+                    .unwrap_or_default();
+                ProxyAuth::new(username, password)
+            });
+        let no_proxy = value("systemProp.http.nonProxyHosts")
+            .map(|hosts| no_proxy_from_java(&hosts))
+            .unwrap_or_default();
+
+        Some(ProxySettings {
+            host,
+            port,
+            auth,
+            protocols,
+            no_proxy,
+            pac_url: None,
+        })
+    }
+}
+
+impl ProxyTarget for Gradle {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.properties_file).ok()?;
+        Self::get_properties(&content).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.properties_file).unwrap_or_default();
+        if let Some(parent) = self.properties_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            &self.properties_file,
+            Self::set_properties(&content, setting),
+        )?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.properties_file) {
+            fs::write(&self.properties_file, Self::unset_properties(&content))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_properties() {
+        let content = "# Build tuning\norg.gradle.jvmargs=-Xmx2g\n";
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            auth: None,
+            protocols: Vec::new(),
+            no_proxy: vec!["localhost".to_string(), ".corp".to_string()],
+            pac_url: None,
+        };
+
+        let updated = Gradle::set_properties(content, &setting);
+        assert_eq!(
+            updated,
+            "# Build tuning\n\
+             org.gradle.jvmargs=-Xmx2g\n\
+             systemProp.http.proxyHost=proxy.corp\n\
+             systemProp.http.proxyPort=3128\n\
+             systemProp.http.nonProxyHosts=localhost|*.corp\n\
+             systemProp.https.proxyHost=proxy.corp\n\
+             systemProp.https.proxyPort=3128\n\
+             systemProp.https.nonProxyHosts=localhost|*.corp\n"
+        );
+
+        let parsed = Gradle::get_properties(&updated).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.port, "3128");
+        assert!(parsed.auth.is_none());
+        assert_eq!(parsed.no_proxy, ["localhost", ".corp"]);
+
+        assert_eq!(Gradle::set_properties(&updated, &setting), updated);
+        assert_eq!(Gradle::unset_properties(&updated), content);
+    }
+
+    #[test]
+    fn test_socks_properties() {
+        let setting = ProxySettings {
+            host: "socks.corp".to_string(),
+            port: "1080".to_string(),
+            auth: Some(ProxyAuth::new("jane".to_string(), "secret".to_string())),
+            protocols: vec![ProxyProtocol::Socks],
+            no_proxy: Vec::new(),
+            pac_url: None,
+        };
+
+        let updated = Gradle::set_properties("", &setting);
+        assert_eq!(
+            updated,
+            "systemProp.socksProxyHost=socks.corp\nsystemProp.socksProxyPort=1080\n"
+        );
+
+        let parsed = Gradle::get_properties(&updated).unwrap();
+        assert_eq!(parsed.host, "socks.corp");
+        assert_eq!(parsed.protocols, [ProxyProtocol::Socks]);
+    }
+}
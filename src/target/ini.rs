@@ -0,0 +1,173 @@
+//! Line-based editing of INI-style files that keeps comments, ordering and
+//! unrelated keys untouched.
+
+fn section_name(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix('[')?
+        .strip_suffix(']')
+        .map(str::trim)
+}
+
+fn key_value(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.starts_with('#') || line.starts_with(';') {
+        return None;
+    }
+    line.split_once('=')
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+/// Returns the line range of `section`, header excluded.
+fn section_range(lines: &[&str], section: &str) -> Option<(usize, usize)> {
+    let header = lines
+        .iter()
+        .position(|line| section_name(line) == Some(section))?;
+    let end = lines[header + 1..]
+        .iter()
+        .position(|line| section_name(line).is_some())
+        .map_or(lines.len(), |offset| header + 1 + offset);
+    Some((header + 1, end))
+}
+
+fn join(lines: &[&str]) -> String {
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    content
+}
+
+/// Names of all sections, in file order.
+pub fn sections(content: &str) -> Vec<&str> {
+    content.lines().filter_map(section_name).collect()
+}
+
+pub fn get_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let lines = content.lines().collect::<Vec<&str>>();
+    let (start, end) = section_range(&lines, section)?;
+    lines[start..end]
+        .iter()
+        .filter_map(|line| key_value(line))
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Sets `key` in `section`, replacing an existing value in place, appending
+/// to the section, or adding the section at the end of the file.
+pub fn set_value(content: &str, section: &str, key: &str, value: &str) -> String {
+    let entry = format!("{} = {}", key, value);
+    let mut lines = content.lines().collect::<Vec<&str>>();
+
+    match section_range(&lines, section) {
+        Some((start, end)) => {
+            let existing = (start..end)
+                .find(|&index| key_value(lines[index]).is_some_and(|(name, _)| name == key));
+            match existing {
+                Some(index) => lines[index] = &entry,
+                None => {
+                    // Keep blank lines that separate this section from the next.
+                    let insert_at = (start..end)
+                        .rev()
+                        .find(|&index| !lines[index].trim().is_empty())
+                        .map_or(start, |index| index + 1);
+                    lines.insert(insert_at, &entry);
+                }
+            }
+        }
+        None => {
+            let header = format!("[{}]", section);
+            let mut content = join(&lines);
+            if !content.is_empty() && !content.ends_with("\n\n") {
+                content.push('\n');
+            }
+            content.push_str(&format!("{}\n{}\n", header, entry));
+            return content;
+        }
+    }
+
+    join(&lines)
+}
+
+/// Removes `key` from `section`, and the section itself once it holds
+/// nothing else.
+pub fn remove_value(content: &str, section: &str, key: &str) -> String {
+    let mut lines = content.lines().collect::<Vec<&str>>();
+    let (start, end) = match section_range(&lines, section) {
+        Some(range) => range,
+        None => return content.to_string(),
+    };
+
+    let kept = lines[start..end]
+        .iter()
+        .filter(|line| key_value(line).is_none_or(|(name, _)| name != key))
+        .copied()
+        .collect::<Vec<&str>>();
+
+    if kept.iter().all(|line| line.trim().is_empty()) {
+        let last_section = end == lines.len();
+        lines.drain(start - 1..end);
+        if last_section {
+            while lines.last().is_some_and(|line| line.trim().is_empty()) {
+                lines.pop();
+            }
+        }
+    } else {
+        lines.splice(start..end, kept);
+    }
+
+    join(&lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_remove_value() {
+        let content = "; user settings\n\
+                       [global]\n\
+                       timeout = 60\n\
+                       \n\
+                       [install]\n\
+                       user = true\n";
+
+        let updated = set_value(content, "global", "proxy", "http://proxy.corp:3128");
+        assert_eq!(
+            updated,
+            "; user settings\n\
+             [global]\n\
+             timeout = 60\n\
+             proxy = http://proxy.corp:3128\n\
+             \n\
+             [install]\n\
+             user = true\n"
+        );
+        assert_eq!(
+            get_value(&updated, "global", "proxy").as_deref(),
+            Some("http://proxy.corp:3128")
+        );
+        assert_eq!(get_value(&updated, "install", "proxy"), None);
+
+        let replaced = set_value(&updated, "global", "proxy", "http://other:8080");
+        assert_eq!(
+            get_value(&replaced, "global", "proxy").as_deref(),
+            Some("http://other:8080")
+        );
+        assert_eq!(remove_value(&replaced, "global", "proxy"), content);
+    }
+
+    #[test]
+    fn test_added_section_is_removed_again() {
+        let content = "[install]\nuser = true\n";
+        let updated = set_value(content, "global", "proxy", "http://proxy.corp:3128");
+        assert_eq!(
+            updated,
+            "[install]\nuser = true\n\n[global]\nproxy = http://proxy.corp:3128\n"
+        );
+        assert_eq!(remove_value(&updated, "global", "proxy"), content);
+
+        let created = set_value("", "global", "proxy", "http://proxy.corp:3128");
+        assert_eq!(created, "[global]\nproxy = http://proxy.corp:3128\n");
+        assert_eq!(remove_value(&created, "global", "proxy"), "");
+    }
+}
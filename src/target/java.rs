@@ -0,0 +1,331 @@
+use crate::{
+    config::JavaConfig,
+    proxy::{ProxyProtocol, ProxySettings},
+    target::{environment, java_non_proxy_hosts, no_proxy_from_java, properties, ProxyTarget},
+};
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+const TOOL_OPTIONS: &str = "JAVA_TOOL_OPTIONS";
+const USE_SYSTEM_PROXIES: &str = "java.net.useSystemProxies";
+const PROXY_KEYS: [&str; 7] = [
+    "http.proxyHost",
+    "http.proxyPort",
+    "https.proxyHost",
+    "https.proxyPort",
+    "http.nonProxyHosts",
+    "socksProxyHost",
+    "socksProxyPort",
+];
+
+/// JVM-wide proxy properties, appended to `JAVA_TOOL_OPTIONS` in an
+/// environment.d snippet so that every JVM started in the session picks
+/// them up, and optionally written to a JDK's `conf/net.properties`.
+pub struct Java {
+    env_file: PathBuf,
+    net_properties_file: Option<PathBuf>,
+    use_system_proxies: bool,
+    update_user_manager: bool,
+}
+
+impl Java {
+    pub fn new(env_file: PathBuf, config: &JavaConfig, update_user_manager: bool) -> Self {
+        Self {
+            env_file,
+            update_user_manager,
+            net_properties_file: config
+                .java_home
+                .as_ref()
+                .map(|java_home| java_home.join("conf/net.properties")),
+            use_system_proxies: config.use_system_proxies,
+        }
+    }
+
+    pub fn env_file() -> PathBuf {
+        dirs::config_dir()
+            .unwrap()
+            .join("environment.d/60-auto-proxy-java.conf")
+    }
+
+    fn java_properties(&self, setting: &ProxySettings) -> Vec<(&'static str, String)> {
+        if self.use_system_proxies {
+            return vec![(USE_SYSTEM_PROXIES, "true".to_string())];
+        }
+
+        let mut entries = Vec::new();
+        let socks_only = setting.socks_only();
+        if !socks_only {
+            entries.push(("http.proxyHost", setting.host.clone()));
+            entries.push(("http.proxyPort", setting.port.clone()));
+            entries.push(("https.proxyHost", setting.host.clone()));
+            entries.push(("https.proxyPort", setting.port.clone()));
+        }
+        if setting.protocols.contains(&ProxyProtocol::Socks) {
+            entries.push(("socksProxyHost", setting.host.clone()));
+            entries.push(("socksProxyPort", setting.port.clone()));
+        }
+        if !setting.no_proxy.is_empty() {
+            entries.push((
+                "http.nonProxyHosts",
+                java_non_proxy_hosts(&setting.no_proxy),
+            ));
+        }
+        entries
+    }
+
+    fn tool_options(&self, setting: &ProxySettings) -> Vec<String> {
+        self.java_properties(setting)
+            .iter()
+            .map(|(key, value)| format!("-D{}={}", key, value))
+            .collect()
+    }
+
+    /// Extends whatever `JAVA_TOOL_OPTIONS` earlier environment.d files set.
+    fn generate_env_content(&self, setting: &ProxySettings) -> String {
+        format!(
+            "# Managed by auto-proxy\n{name}=\"${{{name}}} {}\"\n",
+            self.tool_options(setting).join(" "),
+            name = TOOL_OPTIONS
+        )
+    }
+
+    /// The options in `existing` other than the properties managed here.
+    fn other_tool_options(existing: &str) -> Vec<&str> {
+        existing
+            .split_whitespace()
+            .filter(|option| {
+                !option
+                    .strip_prefix("-D")
+                    .and_then(|option| option.split_once('='))
+                    .is_some_and(|(key, _)| key == USE_SYSTEM_PROXIES || PROXY_KEYS.contains(&key))
+            })
+            .collect()
+    }
+
+    /// Updates `JAVA_TOOL_OPTIONS` in the running user manager, keeping the
+    /// options it had apart from the proxy properties.
+    fn push_to_user_manager(&self, options: &[String]) -> Result<(), Box<dyn Error>> {
+        let existing = env::var(TOOL_OPTIONS).unwrap_or_default();
+        let mut merged = Self::other_tool_options(&existing)
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>();
+        merged.extend_from_slice(options);
+        if merged.is_empty() {
+            return environment::remove_from_user_manager(&[TOOL_OPTIONS.to_string()]);
+        }
+        environment::push_to_user_manager(&[(TOOL_OPTIONS.to_string(), merged.join(" "))])
+    }
+
+    fn parse_env_content(content: &str) -> Option<ProxySettings> {
+        let options = content
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(TOOL_OPTIONS)?.strip_prefix('='))?
+            .trim_matches('"');
+        let property = |key: &str| {
+            options
+                .split_whitespace()
+                .filter_map(|option| option.strip_prefix("-D")?.split_once('='))
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value)
+        };
+        Self::proxy_from(property)
+    }
+
+    /// Reads settings back from proxy properties, whether they came from
+    /// `JAVA_TOOL_OPTIONS` or `net.properties`.
+    fn proxy_from<'a>(property: impl Fn(&str) -> Option<&'a str>) -> Option<ProxySettings> {
+        let (host, port, protocols) = match property("https.proxyHost") {
+            Some(host) => (
+                host,
+                property("https.proxyPort")?,
+                vec![ProxyProtocol::Http, ProxyProtocol::Https],
+            ),
+            None => (
+                property("socksProxyHost")?,
+                property("socksProxyPort")?,
+                vec![ProxyProtocol::Socks],
+            ),
+        };
+
+        Some(ProxySettings {
+            host: host.to_string(),
+            port: port.to_string(),
+            auth: None,
+            protocols,
+            no_proxy: property("http.nonProxyHosts")
+                .map(no_proxy_from_java)
+                .unwrap_or_default(),
+            pac_url: None,
+        })
+    }
+
+    fn set_net_properties(&self, content: &str, setting: &ProxySettings) -> String {
+        let entries = self.java_properties(setting);
+        let mut keys = PROXY_KEYS.to_vec();
+        if self.use_system_proxies {
+            keys.push(USE_SYSTEM_PROXIES);
+        }
+        properties::replace_keys(content, &keys, &entries)
+    }
+
+    /// Removes the proxy properties. `java.net.useSystemProxies` is put back
+    /// to the JDK's shipped `false` rather than removed.
+    fn unset_net_properties(&self, content: &str) -> String {
+        if self.use_system_proxies {
+            return properties::replace_keys(
+                content,
+                &[USE_SYSTEM_PROXIES],
+                &[(USE_SYSTEM_PROXIES, "false".to_string())],
+            );
+        }
+        properties::remove_keys(content, &PROXY_KEYS)
+    }
+}
+
+impl ProxyTarget for Java {
+    fn name(&self) -> &'static str {
+        "java"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let proxy_settings = match fs::read_to_string(&self.env_file) {
+            Ok(content) => Self::parse_env_content(&content),
+            Err(_) => {
+                let content = fs::read_to_string(self.net_properties_file.as_ref()?).ok()?;
+                Self::proxy_from(|key| properties::get_value(&content, key))
+            }
+        };
+        proxy_settings.map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        if let Some(parent) = self.env_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.env_file, self.generate_env_content(setting))?;
+        if self.update_user_manager {
+            self.push_to_user_manager(&self.tool_options(setting))?;
+        }
+
+        if let Some(net_properties_file) = &self.net_properties_file {
+            let content = fs::read_to_string(net_properties_file)?;
+            fs::write(
+                net_properties_file,
+                self.set_net_properties(&content, setting),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if self.env_file.exists() {
+            fs::remove_file(&self.env_file)?;
+        }
+        if self.update_user_manager {
+            self.push_to_user_manager(&[])?;
+        }
+        if let Some(net_properties_file) = &self.net_properties_file {
+            if let Ok(content) = fs::read_to_string(net_properties_file) {
+                fs::write(net_properties_file, self.unset_net_properties(&content))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn follow_up(&self) -> Option<String> {
+        if !self.update_user_manager {
+            return Some(
+                "JAVA_TOOL_OPTIONS applies to sessions started after the change".to_string(),
+            );
+        }
+        Some("JVMs already running keep their old proxy".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn office_settings() -> ProxySettings {
+        ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            auth: None,
+            protocols: Vec::new(),
+            no_proxy: vec!["localhost".to_string(), ".corp".to_string()],
+            pac_url: None,
+        }
+    }
+
+    #[test]
+    fn test_java_tool_options() {
+        let java = Java::new(PathBuf::new(), &JavaConfig::default(), false);
+
+        let content = java.generate_env_content(&office_settings());
+        assert_eq!(
+            content,
+            "# Managed by auto-proxy\n\
+             JAVA_TOOL_OPTIONS=\"${JAVA_TOOL_OPTIONS} -Dhttp.proxyHost=proxy.corp -Dhttp.proxyPort=3128 \
+             -Dhttps.proxyHost=proxy.corp -Dhttps.proxyPort=3128 \
+             -Dhttp.nonProxyHosts=localhost|*.corp\"\n"
+        );
+
+        let parsed = Java::parse_env_content(&content).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.port, "3128");
+        assert_eq!(parsed.no_proxy, ["localhost", ".corp"]);
+
+        assert_eq!(
+            Java::other_tool_options(
+                "-Xmx2g -Dhttp.proxyHost=old.corp -Dfile.encoding=UTF-8 -Dhttp.proxyPort=80"
+            ),
+            ["-Xmx2g", "-Dfile.encoding=UTF-8"]
+        );
+    }
+
+    #[test]
+    fn test_net_properties() {
+        let content = "# java.net.useSystemProxies\njava.net.useSystemProxies=false\n\
+                       #http.proxyHost=\n";
+
+        let java = Java::new(PathBuf::new(), &JavaConfig::default(), false);
+        let updated = java.set_net_properties(content, &office_settings());
+        assert_eq!(
+            properties::get_value(&updated, "http.nonProxyHosts"),
+            Some("localhost|*.corp")
+        );
+        assert_eq!(
+            properties::get_value(&updated, USE_SYSTEM_PROXIES),
+            Some("false")
+        );
+        assert_eq!(java.unset_net_properties(&updated), content);
+
+        let system = Java::new(
+            PathBuf::new(),
+            &JavaConfig {
+                java_home: None,
+                use_system_proxies: true,
+            },
+            false,
+        );
+        let updated = system.set_net_properties(content, &office_settings());
+        assert_eq!(
+            properties::get_value(&updated, USE_SYSTEM_PROXIES),
+            Some("true")
+        );
+        assert_eq!(properties::get_value(&updated, "http.proxyHost"), None);
+        assert_eq!(
+            properties::get_value(&system.unset_net_properties(&updated), USE_SYSTEM_PROXIES),
+            Some("false")
+        );
+    }
+}
@@ -0,0 +1,525 @@
+use crate::{
+    proxy::{ProxyProtocol, ProxySettings},
+    target::ProxyTarget,
+};
+
+use regex::{Captures, Regex};
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// The options written to each `proxy.settings.xml`, by path.
+type Record = BTreeMap<PathBuf, Vec<(String, String)>>;
+
+/// The HTTP Proxy settings of every JetBrains IDE, kept in
+/// `options/proxy.settings.xml` of each versioned config directory. Only the
+/// options below are edited; the rest of the `HttpConfigurable` component is
+/// the IDE's. The IDE rewrites the file on exit, so it only picks up a change
+/// made while closed or after a restart.
+///
+/// What was written is recorded, so that an IDE whose proxy the user set by
+/// hand is left alone and `unset` only removes options that are still ours.
+pub struct JetBrains {
+    config_dir: PathBuf,
+    record_file: PathBuf,
+    skipped: RefCell<Vec<String>>,
+}
+
+impl JetBrains {
+    const OPTIONS: [&'static str; 9] = [
+        "USE_HTTP_PROXY",
+        "USE_PROXY_PAC",
+        "USE_PAC_URL",
+        "PAC_URL",
+        "PROXY_TYPE_IS_SOCKS",
+        "PROXY_HOST",
+        "PROXY_PORT",
+        "PROXY_EXCEPTIONS",
+        "PROXY_AUTHENTICATION",
+    ];
+
+    pub fn new(config_dir: PathBuf, record_file: PathBuf) -> Self {
+        Self {
+            config_dir,
+            record_file,
+            skipped: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn config_dir() -> PathBuf {
+        dirs::config_dir().unwrap().join("JetBrains")
+    }
+
+    pub fn record_file() -> PathBuf {
+        dirs::home_dir().unwrap().join(".auto-proxy/jetbrains.json")
+    }
+
+    fn load_record(&self) -> Result<Record, Box<dyn Error>> {
+        match fs::read_to_string(&self.record_file) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(_) => Ok(Record::new()),
+        }
+    }
+
+    fn save_record(&self, record: &Record) -> Result<(), Box<dyn Error>> {
+        if record.is_empty() {
+            if self.record_file.exists() {
+                fs::remove_file(&self.record_file)?;
+            }
+            return Ok(());
+        }
+        if let Some(parent) = self.record_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.record_file, serde_json::to_string_pretty(record)?)?;
+        Ok(())
+    }
+
+    /// `proxy.settings.xml` of each IDE version that has an `options`
+    /// directory.
+    fn settings_files(&self) -> Vec<PathBuf> {
+        let mut files = fs::read_dir(&self.config_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path().join("options")))
+                    .filter(|options| options.is_dir())
+                    .map(|options| options.join("proxy.settings.xml"))
+                    .collect::<Vec<PathBuf>>()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+
+    fn escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    fn unescape(value: &str) -> String {
+        value
+            .replace("&quot;", "\"")
+            .replace("&gt;", ">")
+            .replace("&lt;", "<")
+            .replace("&amp;", "&")
+    }
+
+    fn generate_options(setting: &ProxySettings) -> Vec<(&'static str, String)> {
+        let mut options = Vec::new();
+        match &setting.pac_url {
+            Some(pac_url) => {
+                options.push(("USE_PROXY_PAC", "true".to_string()));
+                options.push(("USE_PAC_URL", "true".to_string()));
+                options.push(("PAC_URL", pac_url.clone()));
+            }
+            None => {
+                let socks = setting.socks_only();
+                options.push(("USE_HTTP_PROXY", "true".to_string()));
+                if socks {
+                    options.push(("PROXY_TYPE_IS_SOCKS", "true".to_string()));
+                }
+                options.push(("PROXY_HOST", setting.host.clone()));
+                options.push(("PROXY_PORT", setting.port.clone()));
+                if !setting.no_proxy.is_empty() {
+                    options.push(("PROXY_EXCEPTIONS", setting.no_proxy.join(", ")));
+                }
+                // The IDE asks for the password itself and keeps it in its
+                // own credential store.
+                if setting.auth.is_some() {
+                    options.push(("PROXY_AUTHENTICATION", "true".to_string()));
+                }
+            }
+        }
+        options
+    }
+
+    /// Body of the `HttpConfigurable` component, from after its opening tag
+    /// to its closing tag.
+    fn component(content: &str) -> Option<Range<usize>> {
+        let component =
+            Regex::new(r#"(?s)<component\s+name="HttpConfigurable"\s*>(.*?)</component>"#).unwrap();
+        component
+            .captures(content)
+            .and_then(|captures| captures.get(1))
+            .map(|body| body.range())
+    }
+
+    /// Drops the managed options for which `remove` holds from the
+    /// component, with their lines.
+    fn remove_options(content: &str, remove: impl Fn(&str, &str) -> bool) -> String {
+        let body = match Self::component(content) {
+            Some(body) => body,
+            None => return content.to_string(),
+        };
+        let option = Regex::new(&format!(
+            r#"(?m)^[ \t]*<option\s+name="({})"\s+value="([^"]*)"\s*/>[ \t]*\n?"#,
+            Self::OPTIONS.join("|")
+        ))
+        .unwrap();
+        let mut content = content.to_string();
+        let kept = option
+            .replace_all(&content[body.clone()], |captures: &Captures| {
+                if remove(&captures[1], &Self::unescape(&captures[2])) {
+                    String::new()
+                } else {
+                    captures[0].to_string()
+                }
+            })
+            .into_owned();
+        content.replace_range(body, &kept);
+        content
+    }
+
+    /// The managed options the file currently holds.
+    fn managed_options(content: &str) -> Vec<(String, String)> {
+        match Self::component(content) {
+            Some(body) => Self::options(&content[body])
+                .into_iter()
+                .filter(|(name, _)| Self::OPTIONS.contains(&name.as_str()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The name of the IDE version owning `path`, such as `IntelliJIdea2024.1`.
+    fn ide_name(path: &Path) -> String {
+        path.ancestors()
+            .nth(2)
+            .and_then(|dir| dir.file_name())
+            .map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            )
+    }
+
+    fn set_settings(content: &str, setting: &ProxySettings) -> Result<String, Box<dyn Error>> {
+        let options = Self::generate_options(setting)
+            .into_iter()
+            .map(|(name, value)| {
+                format!(
+                    "    <option name=\"{}\" value=\"{}\" />\n",
+                    name,
+                    Self::escape(&value)
+                )
+            })
+            .collect::<String>();
+
+        let mut content = Self::remove_options(content, |_, _| true);
+        if let Some(body) = Self::component(&content) {
+            let close = content[..body.end]
+                .rfind('\n')
+                .map_or(body.end, |newline| newline + 1)
+                .max(body.start);
+            let options = if content[..close].ends_with('\n') {
+                options
+            } else {
+                format!("\n{}  ", options)
+            };
+            content.insert_str(close, &options);
+        } else if let Some(close) = content.rfind("</application>") {
+            content.insert_str(
+                close,
+                &format!(
+                    "  <component name=\"HttpConfigurable\">\n{}  </component>\n",
+                    options
+                ),
+            );
+        } else if content.trim().is_empty() {
+            content = format!(
+                "<application>\n  <component name=\"HttpConfigurable\">\n{}  </component>\n</application>\n",
+                options
+            );
+        } else {
+            return Err("no <application> element".into());
+        }
+        Ok(content)
+    }
+
+    fn options(content: &str) -> Vec<(String, String)> {
+        let option = Regex::new(r#"<option\s+name="(\w+)"\s+value="([^"]*)"\s*/>"#).unwrap();
+        option
+            .captures_iter(content)
+            .map(|captures| (captures[1].to_string(), Self::unescape(&captures[2])))
+            .collect()
+    }
+
+    fn parse_settings(content: &str) -> Option<ProxySettings> {
+        let options = Self::options(&content[Self::component(content)?]);
+        let value = |name: &str| {
+            options
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        if value("USE_PROXY_PAC") == Some("true") {
+            return Some(ProxySettings {
+                pac_url: value("PAC_URL").map(String::from),
+                ..Default::default()
+            });
+        }
+        if value("USE_HTTP_PROXY") != Some("true") {
+            return None;
+        }
+        Some(ProxySettings {
+            host: value("PROXY_HOST")?.to_string(),
+            port: value("PROXY_PORT").unwrap_or("80").to_string(),
+            protocols: if value("PROXY_TYPE_IS_SOCKS") == Some("true") {
+                vec![ProxyProtocol::Socks]
+            } else {
+                Vec::new()
+            },
+            no_proxy: value("PROXY_EXCEPTIONS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .map(String::from)
+                .collect(),
+            ..Default::default()
+        })
+    }
+}
+
+impl ProxyTarget for JetBrains {
+    fn name(&self) -> &'static str {
+        "jetbrains"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let proxy_settings = self
+            .settings_files()
+            .iter()
+            .rev()
+            .find_map(|path| Self::parse_settings(&fs::read_to_string(path).ok()?))?;
+        Some(vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let mut record = self.load_record()?;
+        let mut skipped = Vec::new();
+        for path in self.settings_files() {
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            let written = record.get(&path).cloned().unwrap_or_default();
+            if !Self::managed_options(&content)
+                .iter()
+                .all(|option| written.contains(option))
+            {
+                skipped.push(Self::ide_name(&path));
+                continue;
+            }
+
+            let updated = Self::set_settings(&content, setting)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            if updated != content {
+                fs::write(&path, updated)?;
+            }
+            let options = Self::generate_options(setting)
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect();
+            record.insert(path, options);
+        }
+        self.skipped.replace(skipped);
+        self.save_record(&record)
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        self.skipped.replace(Vec::new());
+        for (path, written) in self.load_record()? {
+            if let Ok(content) = fs::read_to_string(&path) {
+                let updated = Self::remove_options(&content, |name, value| {
+                    written.iter().any(|(written_name, written_value)| {
+                        written_name == name && written_value == value
+                    })
+                });
+                if updated != content {
+                    fs::write(path, updated)?;
+                }
+            }
+        }
+        self.save_record(&Record::new())
+    }
+
+    fn supports_pac(&self) -> bool {
+        true
+    }
+
+    /// Also asks for the credentials when the proxy needs them, since the
+    /// IDE keeps those in its own password store.
+    fn follow_up(&self) -> Option<String> {
+        let authenticated = self.settings_files().iter().any(|path| {
+            fs::read_to_string(path).is_ok_and(|content| {
+                Self::options(&content)
+                    .iter()
+                    .any(|(name, value)| name == "PROXY_AUTHENTICATION" && value == "true")
+            })
+        });
+        let mut follow_up = "Restart JetBrains IDEs to pick up the new proxy".to_string();
+        if authenticated {
+            follow_up.push_str(", and enter the proxy credentials when the IDE asks");
+        }
+        let skipped = self.skipped.borrow();
+        if !skipped.is_empty() {
+            follow_up.push_str(&format!(
+                "; left the proxy set by hand in {} alone",
+                skipped.join(", ")
+            ));
+        }
+        Some(follow_up)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::ProxyAuth;
+    use std::env;
+
+    #[test]
+    fn test_proxy_settings_xml() {
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "1080".to_string(),
+            protocols: vec![ProxyProtocol::Socks],
+            no_proxy: vec!["localhost".to_string(), ".corp".to_string()],
+            auth: Some(ProxyAuth::new("alice".to_string(), "s3cret".to_string())),
+            ..Default::default()
+        };
+        let content = JetBrains::set_settings("", &setting).unwrap();
+        assert_eq!(
+            content,
+            r#"<application>
+  <component name="HttpConfigurable">
+    <option name="USE_HTTP_PROXY" value="true" />
+    <option name="PROXY_TYPE_IS_SOCKS" value="true" />
+    <option name="PROXY_HOST" value="proxy.corp" />
+    <option name="PROXY_PORT" value="1080" />
+    <option name="PROXY_EXCEPTIONS" value="localhost, .corp" />
+    <option name="PROXY_AUTHENTICATION" value="true" />
+  </component>
+</application>
+"#
+        );
+
+        let parsed = JetBrains::parse_settings(&content).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.port, "1080");
+        assert_eq!(parsed.protocols, [ProxyProtocol::Socks]);
+        assert_eq!(parsed.no_proxy, ["localhost", ".corp"]);
+        assert!(parsed.auth.is_none());
+
+        let pac = ProxySettings {
+            pac_url: Some("http://wpad.corp/proxy.pac?a=1&b=2".to_string()),
+            ..Default::default()
+        };
+        let content = JetBrains::set_settings(&content, &pac).unwrap();
+        assert!(content.contains(r#"value="http://wpad.corp/proxy.pac?a=1&amp;b=2""#));
+        assert_eq!(
+            JetBrains::parse_settings(&content).unwrap().pac_url,
+            pac.pac_url
+        );
+    }
+
+    #[test]
+    fn test_other_options_untouched() {
+        let content = r#"<application>
+  <component name="HttpConfigurable">
+    <option name="PROXY_HOST" value="old.corp" />
+    <option name="USE_HTTP_PROXY" value="true" />
+    <option name="CHECK_CONNECTION_URL" value="https://example.org" />
+  </component>
+  <component name="Other">
+    <option name="PROXY_HOST" value="kept" />
+  </component>
+</application>
+"#;
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            ..Default::default()
+        };
+
+        let updated = JetBrains::set_settings(content, &setting).unwrap();
+        assert_eq!(
+            updated,
+            r#"<application>
+  <component name="HttpConfigurable">
+    <option name="CHECK_CONNECTION_URL" value="https://example.org" />
+    <option name="USE_HTTP_PROXY" value="true" />
+    <option name="PROXY_HOST" value="proxy.corp" />
+    <option name="PROXY_PORT" value="3128" />
+  </component>
+  <component name="Other">
+    <option name="PROXY_HOST" value="kept" />
+  </component>
+</application>
+"#
+        );
+        assert_eq!(
+            JetBrains::parse_settings(&updated).unwrap().host,
+            "proxy.corp"
+        );
+
+        let unset = JetBrains::remove_options(&updated, |_, _| true);
+        assert!(unset.contains(r#"<option name="CHECK_CONNECTION_URL""#));
+        assert!(unset.contains(r#"<option name="PROXY_HOST" value="kept" />"#));
+        assert!(JetBrains::parse_settings(&unset).is_none());
+    }
+
+    #[test]
+    fn test_only_own_options() {
+        let dir = env::temp_dir().join(format!("auto-proxy-jetbrains-{}", std::process::id()));
+        let ours = dir.join("IntelliJIdea2024.1/options/proxy.settings.xml");
+        let theirs = dir.join("PyCharm2024.1/options/proxy.settings.xml");
+        fs::create_dir_all(ours.parent().unwrap()).unwrap();
+        fs::create_dir_all(theirs.parent().unwrap()).unwrap();
+        let hand_set = "<application>\n  <component name=\"HttpConfigurable\">\n    \
+                        <option name=\"USE_HTTP_PROXY\" value=\"true\" />\n    \
+                        <option name=\"PROXY_HOST\" value=\"other.corp\" />\n  \
+                        </component>\n</application>\n";
+        fs::write(&theirs, hand_set).unwrap();
+
+        let jetbrains = JetBrains::new(dir.clone(), dir.join("jetbrains.json"));
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            ..Default::default()
+        };
+        jetbrains.set(vec![&setting]).unwrap();
+        assert!(jetbrains.follow_up().unwrap().contains("PyCharm2024.1"));
+        assert_eq!(fs::read_to_string(&theirs).unwrap(), hand_set);
+        let content = fs::read_to_string(&ours).unwrap();
+        assert_eq!(
+            JetBrains::parse_settings(&content).unwrap().host,
+            "proxy.corp"
+        );
+
+        // An exception added in the IDE afterwards survives the unset.
+        let content = content.replace(
+            "  </component>",
+            "    <option name=\"PROXY_EXCEPTIONS\" value=\"localhost\" />\n  </component>",
+        );
+        fs::write(&ours, &content).unwrap();
+        jetbrains.unset().unwrap();
+        let content = fs::read_to_string(&ours).unwrap();
+        assert!(content.contains("PROXY_EXCEPTIONS"));
+        assert!(!content.contains("PROXY_HOST"));
+        assert_eq!(fs::read_to_string(&theirs).unwrap(), hand_set);
+        assert!(!dir.join("jetbrains.json").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,362 @@
+//! Editing of JSON with comments and trailing commas, the dialect VS Code
+//! keeps its settings in. Top-level members are replaced in place so that
+//! comments, ordering and formatting of the rest of the file survive.
+
+use crate::target::parse_json_object;
+
+use serde::Serialize;
+use serde_json::{ser::PrettyFormatter, Map, Serializer, Value};
+
+use std::error::Error;
+use std::ops::Range;
+
+struct Member {
+    key: String,
+    /// Offset of the opening quote of the key.
+    start: usize,
+    value: Range<usize>,
+    comma: Option<usize>,
+}
+
+struct Object {
+    open: usize,
+    close: usize,
+    members: Vec<Member>,
+}
+
+/// Skips whitespace and comments.
+fn skip_blank(bytes: &[u8], mut pos: usize) -> usize {
+    loop {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if bytes[pos..].starts_with(b"//") {
+            pos = bytes[pos..]
+                .iter()
+                .position(|&byte| byte == b'\n')
+                .map_or(bytes.len(), |offset| pos + offset);
+        } else if bytes[pos..].starts_with(b"/*") {
+            pos = bytes[pos + 2..]
+                .windows(2)
+                .position(|window| window == b"*/")
+                .map_or(bytes.len(), |offset| pos + 2 + offset + 2);
+        } else {
+            return pos;
+        }
+    }
+}
+
+fn string_end(bytes: &[u8], mut pos: usize) -> Result<usize, Box<dyn Error>> {
+    pos += 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'"' => return Ok(pos + 1),
+            _ => pos += 1,
+        }
+    }
+    Err("unterminated string in JSON".into())
+}
+
+fn value_end(bytes: &[u8], pos: usize) -> Result<usize, Box<dyn Error>> {
+    match bytes.get(pos) {
+        Some(b'"') => string_end(bytes, pos),
+        Some(b'{') | Some(b'[') => {
+            let mut depth = 0;
+            let mut pos = pos;
+            while pos < bytes.len() {
+                match bytes[pos] {
+                    b'"' => pos = string_end(bytes, pos)?,
+                    b'/' => pos = skip_blank(bytes, pos).max(pos + 1),
+                    b'{' | b'[' => {
+                        depth += 1;
+                        pos += 1;
+                    }
+                    b'}' | b']' => {
+                        depth -= 1;
+                        pos += 1;
+                        if depth == 0 {
+                            return Ok(pos);
+                        }
+                    }
+                    _ => pos += 1,
+                }
+            }
+            Err("unterminated value in JSON".into())
+        }
+        _ => {
+            let end = bytes[pos..]
+                .iter()
+                .position(|&byte| {
+                    matches!(byte, b',' | b'}' | b']' | b'/') || byte.is_ascii_whitespace()
+                })
+                .map_or(bytes.len(), |offset| pos + offset);
+            if end == pos {
+                return Err(format!("expected a value at byte {}", pos).into());
+            }
+            Ok(end)
+        }
+    }
+}
+
+fn scan(content: &str) -> Result<Object, Box<dyn Error>> {
+    let bytes = content.as_bytes();
+    let open = skip_blank(bytes, 0);
+    if bytes.get(open) != Some(&b'{') {
+        return Err("expected a JSON object".into());
+    }
+
+    let mut members = Vec::new();
+    let mut pos = open + 1;
+    loop {
+        pos = skip_blank(bytes, pos);
+        match bytes.get(pos) {
+            None => return Err("unterminated object in JSON".into()),
+            Some(b'}') => break,
+            Some(b'"') => {}
+            Some(_) => return Err(format!("expected a key at byte {}", pos).into()),
+        }
+        let start = pos;
+        let key_end = string_end(bytes, pos)?;
+        let key: String = serde_json::from_str(&content[start..key_end])?;
+        pos = skip_blank(bytes, key_end);
+        if bytes.get(pos) != Some(&b':') {
+            return Err(format!("expected ':' at byte {}", pos).into());
+        }
+        let value_start = skip_blank(bytes, pos + 1);
+        let value_end = value_end(bytes, value_start)?;
+        pos = skip_blank(bytes, value_end);
+        let comma = (bytes.get(pos) == Some(&b',')).then_some(pos);
+        if comma.is_some() {
+            pos += 1;
+        }
+        members.push(Member {
+            key,
+            start,
+            value: value_start..value_end,
+            comma,
+        });
+    }
+    Ok(Object {
+        open,
+        close: pos,
+        members,
+    })
+}
+
+/// Blanks out comments and drops trailing commas, leaving plain JSON.
+fn strip(content: &str) -> Result<String, Box<dyn Error>> {
+    let bytes = content.as_bytes();
+    let mut plain = String::with_capacity(content.len());
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'"' => {
+                let end = string_end(bytes, pos)?;
+                plain.push_str(&content[pos..end]);
+                pos = end;
+            }
+            b'/' if bytes[pos..].starts_with(b"//") || bytes[pos..].starts_with(b"/*") => {
+                pos = skip_blank(bytes, pos);
+                plain.push(' ');
+            }
+            b',' if matches!(bytes.get(skip_blank(bytes, pos + 1)), Some(b'}' | b']')) => {
+                pos += 1;
+            }
+            _ => {
+                let end = pos + content[pos..].chars().next().unwrap().len_utf8();
+                plain.push_str(&content[pos..end]);
+                pos = end;
+            }
+        }
+    }
+    Ok(plain)
+}
+
+fn line_start(content: &str, pos: usize) -> usize {
+    content[..pos].rfind('\n').map_or(0, |index| index + 1)
+}
+
+fn indent_of(content: &str, pos: usize) -> &str {
+    let start = line_start(content, pos);
+    let line = &content[start..pos];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Renders `key: value` pretty-printed for a member indented by `indent`.
+fn render_member(key: &str, value: &Value, indent: &str) -> Result<String, Box<dyn Error>> {
+    Ok(format!(
+        "{}: {}",
+        serde_json::to_string(key)?,
+        render_value(value, indent)?
+    ))
+}
+
+/// Pretty-prints `value`, nesting by the member's own indentation so that a
+/// file indented with tabs stays that way.
+fn render_value(value: &Value, indent: &str) -> Result<String, Box<dyn Error>> {
+    let unit = if indent.is_empty() { "    " } else { indent };
+    let mut rendered = Vec::new();
+    let formatter = PrettyFormatter::with_indent(unit.as_bytes());
+    value.serialize(&mut Serializer::with_formatter(&mut rendered, formatter))?;
+    Ok(String::from_utf8(rendered)?.replace('\n', &format!("\n{}", indent)))
+}
+
+pub fn parse(content: &str) -> Result<Map<String, Value>, Box<dyn Error>> {
+    parse_json_object(&strip(content)?)
+}
+
+/// Sets the top-level `key`, replacing its value in place or appending it as
+/// the last member.
+pub fn set_value(content: &str, key: &str, value: &Value) -> Result<String, Box<dyn Error>> {
+    if content.trim().is_empty() {
+        return Ok(format!("{{\n{}\n}}\n", render_member(key, value, "    ")?));
+    }
+
+    let object = scan(content)?;
+    let mut content = content.to_string();
+    if let Some(member) = object.members.iter().find(|member| member.key == key) {
+        let indent = indent_of(&content, member.start).to_string();
+        content.replace_range(member.value.clone(), &render_value(value, &indent)?);
+        return Ok(content);
+    }
+
+    match object.members.last() {
+        Some(last) => {
+            let indent = indent_of(&content, last.start).to_string();
+            let after = last.comma.map_or(last.value.end, |comma| comma + 1);
+            let line_end = content[after..]
+                .find('\n')
+                .map_or(object.close, |offset| after + offset)
+                .min(object.close);
+            let mut member = format!("\n{}{}", indent, render_member(key, value, &indent)?);
+            if line_end == object.close {
+                member.push('\n');
+            }
+            content.insert_str(line_end, &member);
+            if last.comma.is_none() {
+                content.insert(last.value.end, ',');
+            }
+        }
+        None => {
+            let indent = format!("{}    ", indent_of(&content, object.open));
+            let member = render_member(key, value, &indent)?;
+            if content[object.open + 1..object.close].trim().is_empty() {
+                content.replace_range(
+                    object.open + 1..object.close,
+                    &format!(
+                        "\n{}{}\n{}",
+                        indent,
+                        member,
+                        indent_of(&content, object.open)
+                    ),
+                );
+            } else {
+                let newline = if content[..object.close].ends_with('\n') {
+                    ""
+                } else {
+                    "\n"
+                };
+                content.insert_str(object.close, &format!("{}{}{}\n", newline, indent, member));
+            }
+        }
+    }
+    Ok(content)
+}
+
+/// Removes the top-level `key` together with its line, if it is alone there.
+pub fn remove_value(content: &str, key: &str) -> Result<String, Box<dyn Error>> {
+    if content.trim().is_empty() {
+        return Ok(content.to_string());
+    }
+
+    let object = scan(content)?;
+    let index = match object.members.iter().position(|member| member.key == key) {
+        Some(index) => index,
+        None => return Ok(content.to_string()),
+    };
+    let member = &object.members[index];
+
+    let mut start = member.start;
+    let mut end = member.comma.map_or(member.value.end, |comma| comma + 1);
+    let own_line = content[line_start(content, start)..start].trim().is_empty();
+    if own_line {
+        start = line_start(content, start);
+        let rest = &content[end..];
+        if let Some(newline) = rest.find('\n') {
+            if rest[..newline].trim().is_empty() {
+                end += newline + 1;
+            }
+        }
+    }
+
+    let mut content = content.to_string();
+    content.replace_range(start..end, "");
+    // The member before the removed last one must not keep its comma.
+    if member.comma.is_none() && index > 0 {
+        if let Some(comma) = object.members[index - 1].comma {
+            content.remove(comma);
+        }
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_jsonc() {
+        let content = r#"{
+    // Editor
+    "editor.fontSize": 14, /* px */
+    "http.proxy": "http://old:8080",
+    "files.exclude": {
+        "**/.git": true,
+    },
+}
+"#;
+        assert_eq!(parse(content).unwrap()["editor.fontSize"], 14);
+
+        let content = set_value(content, "http.proxy", &json!("http://proxy.corp:3128")).unwrap();
+        assert!(content.contains(r#"    "http.proxy": "http://proxy.corp:3128","#));
+
+        let content = set_value(&content, "http.noProxy", &json!(["localhost"])).unwrap();
+        assert!(content.ends_with(
+            r#"    },
+    "http.noProxy": [
+        "localhost"
+    ]
+}
+"#
+        ));
+        assert_eq!(
+            parse(&content).unwrap()["http.noProxy"],
+            json!(["localhost"])
+        );
+
+        let content = remove_value(&content, "http.noProxy").unwrap();
+        let content = remove_value(&content, "http.proxy").unwrap();
+        assert_eq!(
+            content,
+            r#"{
+    // Editor
+    "editor.fontSize": 14, /* px */
+    "files.exclude": {
+        "**/.git": true,
+    }
+}
+"#
+        );
+
+        assert_eq!(
+            set_value("{}", "http.proxy", &json!("http://proxy.corp:3128")).unwrap(),
+            "{\n    \"http.proxy\": \"http://proxy.corp:3128\"\n}"
+        );
+        assert_eq!(
+            set_value(r#"{"a": 1}"#, "b", &json!(2)).unwrap(),
+            "{\"a\": 1,\n\"b\": 2\n}"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! A block of lines delimited by marker comments, so that a target can own
+//! part of a file that the user also edits by hand.
+
+fn markers(comment: &str) -> (String, String) {
+    (
+        format!("{} >>> auto-proxy >>>", comment),
+        format!("{} <<< auto-proxy <<<", comment),
+    )
+}
+
+/// Returns the lines between the markers.
+pub fn get<'a>(content: &'a str, comment: &str) -> Option<&'a str> {
+    let (begin, end) = markers(comment);
+    let start = content.find(&begin)? + begin.len();
+    let start = start + content[start..].find('\n')? + 1;
+    let stop = start + content[start..].find(&end)?;
+    Some(&content[start..stop])
+}
+
+/// Removes the block, markers included.
+pub fn remove(content: &str, comment: &str) -> String {
+    let (begin, end) = markers(comment);
+    let mut result = String::new();
+    let mut inside = false;
+
+    for line in content.lines() {
+        if line.trim() == begin {
+            inside = true;
+        } else if inside && line.trim() == end {
+            inside = false;
+        } else if !inside {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+
+    result
+}
+
+/// Replaces the block with `lines`, or appends it when the file has none.
+pub fn replace(content: &str, comment: &str, lines: &str) -> String {
+    let (begin, end) = markers(comment);
+    let block = format!("{}\n{}{}\n", begin, lines, end);
+
+    if get(content, comment).is_none() {
+        let mut content = content.to_string();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&block);
+        return content;
+    }
+
+    let start = content.find(&begin).unwrap_or_default();
+    let stop = content[start..]
+        .find(&end)
+        .map_or(content.len(), |index| start + index + end.len());
+    let stop = if content[stop..].starts_with('\n') {
+        stop + 1
+    } else {
+        stop
+    };
+    format!("{}{}{}", &content[..start], block, &content[stop..])
+}
+
+/// Moves the block to the top of the file with `lines`, for files where the
+/// first match wins.
+pub fn prepend(content: &str, comment: &str, lines: &str) -> String {
+    let (begin, end) = markers(comment);
+    format!("{}\n{}{}\n{}", begin, lines, end, remove(content, comment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_managed_block() {
+        let content = "--max-time 60\n";
+
+        let updated = replace(content, "#", "proxy = \"http://proxy.corp:3128\"\n");
+        assert_eq!(
+            updated,
+            "--max-time 60\n\
+             # >>> auto-proxy >>>\n\
+             proxy = \"http://proxy.corp:3128\"\n\
+             # <<< auto-proxy <<<\n"
+        );
+        assert_eq!(
+            get(&updated, "#"),
+            Some("proxy = \"http://proxy.corp:3128\"\n")
+        );
+
+        let replaced = replace(&(updated + "--silent\n"), "#", "proxy = \"other:8080\"\n");
+        assert_eq!(
+            replaced,
+            "--max-time 60\n\
+             # >>> auto-proxy >>>\n\
+             proxy = \"other:8080\"\n\
+             # <<< auto-proxy <<<\n\
+             --silent\n"
+        );
+        assert_eq!(remove(&replaced, "#"), "--max-time 60\n--silent\n");
+        assert_eq!(get(content, "#"), None);
+
+        assert_eq!(
+            prepend(&replaced, "#", "proxy = \"other:8080\"\n"),
+            "# >>> auto-proxy >>>\n\
+             proxy = \"other:8080\"\n\
+             # <<< auto-proxy <<<\n\
+             --max-time 60\n\
+             --silent\n"
+        );
+    }
+}
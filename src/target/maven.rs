@@ -0,0 +1,287 @@
+use crate::{
+    proxy::{ProxyAuth, ProxySettings},
+    target::{java_non_proxy_hosts, no_proxy_from_java, ProxyTarget},
+};
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use regex::Regex;
+
+const ID_PREFIX: &str = "auto-proxy";
+const PROTOCOLS: [&str; 2] = ["http", "https"];
+
+/// The `<proxies>` of Maven's user settings. Only `<proxy>` elements whose
+/// id starts with `auto-proxy` are touched; the rest of the XML is kept as
+/// written.
+pub struct Maven {
+    settings_file: PathBuf,
+}
+
+impl Maven {
+    pub fn new(settings_file: PathBuf) -> Self {
+        Self { settings_file }
+    }
+
+    pub fn settings_file() -> PathBuf {
+        dirs::home_dir().unwrap().join(".m2/settings.xml")
+    }
+
+    fn proxy_element() -> Regex {
+        Regex::new(r"(?s)[ \t]*<proxy>.*?</proxy>[ \t]*\r?\n?").unwrap()
+    }
+
+    fn is_managed(element: &str) -> bool {
+        Self::tag_value(element, "id").is_some_and(|id| id.starts_with(ID_PREFIX))
+    }
+
+    fn tag_value(element: &str, tag: &str) -> Option<String> {
+        let start = element.find(&format!("<{}>", tag))? + tag.len() + 2;
+        let end = start + element[start..].find(&format!("</{}>", tag))?;
+        Some(unescape(element[start..end].trim()))
+    }
+
+    fn generate_proxy_elements(setting: &ProxySettings) -> String {
+        let mut content = String::new();
+        for protocol in PROTOCOLS {
+            content.push_str(&format!(
+                "    <proxy>\n      \
+                       <id>{}-{}</id>\n      \
+                       <active>true</active>\n      \
+                       <protocol>{}</protocol>\n      \
+                       <host>{}</host>\n      \
+                       <port>{}</port>\n",
+                ID_PREFIX,
+                protocol,
+                protocol,
+                escape(&setting.host),
+                escape(&setting.port)
+            ));
+            if let Some(auth) = setting
+                .auth
+                .as_ref()
+                .filter(|auth| !auth.username.is_empty())
+            {
+                content.push_str(&format!(
+                    "      <username>{}</username>\n      \
+                           <password>{}</password>\n",
+                    escape(&auth.username),
+                    escape(&auth.password)
+                ));
+            }
+            if !setting.no_proxy.is_empty() {
+                content.push_str(&format!(
+                    "      <nonProxyHosts>{}</nonProxyHosts>\n",
+                    escape(&java_non_proxy_hosts(&setting.no_proxy))
+                ));
+            }
+            content.push_str("    </proxy>\n");
+        }
+        content
+    }
+
+    fn remove_managed_proxies(content: &str) -> String {
+        Self::proxy_element()
+            .replace_all(content, |captures: &regex::Captures| {
+                if Self::is_managed(&captures[0]) {
+                    String::new()
+                } else {
+                    captures[0].to_string()
+                }
+            })
+            .into_owned()
+    }
+
+    /// The managed proxies go ahead of the user's, since Maven takes the
+    /// first active proxy for a protocol.
+    fn set_proxies(content: &str, setting: &ProxySettings) -> Result<String, Box<dyn Error>> {
+        let content = Self::remove_managed_proxies(content);
+        let proxies = Self::generate_proxy_elements(setting);
+
+        if let Some(start) = content.find("<proxies>") {
+            // Insert on the line after the opening tag when it ends there.
+            let after = start + "<proxies>".len();
+            let insert_at = match content[after..].find('\n') {
+                Some(index) if content[after..after + index].trim().is_empty() => after + index + 1,
+                _ => after,
+            };
+            return Ok(format!(
+                "{}{}{}",
+                &content[..insert_at],
+                proxies,
+                &content[insert_at..]
+            ));
+        }
+
+        let proxies = format!("  <proxies>\n{}  </proxies>\n", proxies);
+        let content = content.replacen("<proxies/>", "", 1);
+        if let Some(end) = content.find("</settings>") {
+            return Ok(format!("{}{}{}", &content[..end], proxies, &content[end..]));
+        }
+        if !content.trim().is_empty() {
+            return Err("settings.xml has no <settings> element".into());
+        }
+
+        Ok(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <settings xmlns=\"http://maven.apache.org/SETTINGS/1.2.0\"\n          \
+                       xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\"\n          \
+                       xsi:schemaLocation=\"http://maven.apache.org/SETTINGS/1.2.0 \
+                       https://maven.apache.org/xsd/settings-1.2.0.xsd\">\n\
+             {}</settings>\n",
+            proxies
+        ))
+    }
+
+    /// Removes the managed proxies, and `<proxies>` along with them once
+    /// nothing else is left in it.
+    fn unset_proxies(content: &str) -> String {
+        let content = Self::remove_managed_proxies(content);
+        let empty_proxies = Regex::new(r"[ \t]*<proxies>\s*</proxies>[ \t]*\r?\n?").unwrap();
+        empty_proxies.replace(&content, "").into_owned()
+    }
+
+    fn get_proxies(content: &str) -> Option<ProxySettings> {
+        let regex = Self::proxy_element();
+        let element = regex
+            .find_iter(content)
+            .map(|element| element.as_str())
+            .find(|element| Self::is_managed(element))?;
+
+        let auth = Self::tag_value(element, "username").map(|username| {
+            ProxyAuth::new(
+                username,
+                Self::tag_value(element, "password").unwrap_or_default(),
+            )
+        });
+        let no_proxy = Self::tag_value(element, "nonProxyHosts")
+            .map(|hosts| no_proxy_from_java(&hosts))
+            .unwrap_or_default();
+
+        Some(ProxySettings {
+            host: Self::tag_value(element, "host")?,
+            port: Self::tag_value(element, "port")?,
+            auth,
+            no_proxy,
+            ..Default::default()
+        })
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+impl ProxyTarget for Maven {
+    fn name(&self) -> &'static str {
+        "maven"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.settings_file).ok()?;
+        Self::get_proxies(&content).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.settings_file).unwrap_or_default();
+        if let Some(parent) = self.settings_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.settings_file, Self::set_proxies(&content, setting)?)?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.settings_file) {
+            fs::write(&self.settings_file, Self::unset_proxies(&content))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn office_settings() -> ProxySettings {
+        ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            auth: Some(ProxyAuth::new("jane".to_string(), "s3cret&<".to_string())),
+            protocols: Vec::new(),
+            no_proxy: vec!["localhost".to_string(), ".corp".to_string()],
+            pac_url: None,
+        }
+    }
+
+    #[test]
+    fn test_existing_settings() {
+        let content = "<settings>\n  \
+                         <localRepository>/srv/m2</localRepository>\n  \
+                         <proxies>\n    \
+                           <proxy>\n      \
+                             <id>legacy</id>\n      \
+                             <host>old.corp</host>\n      \
+                             <port>80</port>\n    \
+                           </proxy>\n  \
+                         </proxies>\n\
+                       </settings>\n";
+
+        let updated = Maven::set_proxies(content, &office_settings()).unwrap();
+        assert!(updated.contains("<localRepository>/srv/m2</localRepository>"));
+        assert!(updated.contains("<id>legacy</id>"));
+        assert!(updated.contains(
+            "    <proxy>\n      \
+                   <id>auto-proxy-https</id>\n      \
+                   <active>true</active>\n      \
+                   <protocol>https</protocol>\n      \
+                   <host>proxy.corp</host>\n      \
+                   <port>3128</port>\n      \
+                   <username>jane</username>\n      \
+                   <password>s3cret&amp;&lt;</password>\n      \
+                   <nonProxyHosts>localhost|*.corp</nonProxyHosts>\n    \
+                 </proxy>\n    \
+               <proxy>\n      \
+                 <id>legacy</id>\n"
+        ));
+
+        let parsed = Maven::get_proxies(&updated).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.port, "3128");
+        assert_eq!(parsed.auth.unwrap().password, "s3cret&<");
+        assert_eq!(parsed.no_proxy, ["localhost", ".corp"]);
+
+        // Setting again replaces the managed proxies instead of adding more.
+        let updated = Maven::set_proxies(&updated, &office_settings()).unwrap();
+        assert_eq!(updated.matches("<id>auto-proxy-http</id>").count(), 1);
+
+        assert_eq!(Maven::unset_proxies(&updated), content);
+    }
+
+    #[test]
+    fn test_missing_settings() {
+        let created = Maven::set_proxies("", &office_settings()).unwrap();
+        assert!(created.starts_with("<?xml"));
+        assert_eq!(Maven::get_proxies(&created).unwrap().host, "proxy.corp");
+        assert!(!Maven::unset_proxies(&created).contains("<proxies>"));
+
+        let content = "<settings>\n  <offline>false</offline>\n</settings>\n";
+        let updated = Maven::set_proxies(content, &office_settings()).unwrap();
+        assert!(updated.contains("  <proxies>\n    <proxy>\n"));
+        assert_eq!(Maven::unset_proxies(&updated), content);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    network::{self, ConnectionProxy, SavedConnection},
+    proxy::ProxySettings,
+    proxy_profile::ProxyProfile,
+    target::ProxyTarget,
+};
+
+use std::error::Error;
+
+/// The per-connection `proxy` setting of NetworkManager, which only knows
+/// automatic configuration. Every profile with a `pac_url` attaches it to
+/// the connections in its `auto_apply_networks`, named by id or UUID.
+///
+/// The setting follows the profiles rather than the active one, so `set`
+/// and `unset` both bring every connection in line with them.
+pub struct NetworkManager;
+
+impl NetworkManager {
+    /// The proxy `connection` should carry, or `None` to leave it alone.
+    /// A PAC URL that no profile attaches to it any more is only removed
+    /// when it still belongs to some profile.
+    fn desired_proxy(
+        connection: &SavedConnection,
+        profiles: &[ProxyProfile],
+    ) -> Option<ConnectionProxy> {
+        let attached = profiles.iter().find_map(|profile| {
+            let pac_url = profile.proxy_settings.pac_url.as_ref()?;
+            profile
+                .auto_apply_networks
+                .iter()
+                .any(|network| *network == connection.id || *network == connection.uuid)
+                .then(|| pac_url.clone())
+        });
+
+        let desired = match attached {
+            Some(pac_url) => ConnectionProxy {
+                auto: true,
+                pac_url: Some(pac_url),
+                browser_only: connection.proxy.browser_only,
+            },
+            None => {
+                let ours = connection.proxy.pac_url.as_ref().is_some_and(|url| {
+                    profiles
+                        .iter()
+                        .any(|profile| profile.proxy_settings.pac_url.as_ref() == Some(url))
+                });
+                if !ours {
+                    return None;
+                }
+                ConnectionProxy::default()
+            }
+        };
+        (desired != connection.proxy).then_some(desired)
+    }
+
+    fn sync(&self) -> Result<(), Box<dyn Error>> {
+        let profiles = ProxyProfile::load_all()?;
+        for connection in network::saved_connections()? {
+            if let Some(proxy) = Self::desired_proxy(&connection, &profiles) {
+                network::update_connection_proxy(&connection.path, &proxy)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProxyTarget for NetworkManager {
+    fn name(&self) -> &'static str {
+        "networkmanager"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let mut pac_urls = network::saved_connections()
+            .ok()?
+            .into_iter()
+            .filter(|connection| connection.proxy.auto)
+            .filter_map(|connection| connection.proxy.pac_url)
+            .collect::<Vec<String>>();
+        pac_urls.sort();
+        pac_urls.dedup();
+
+        let proxy_settings = pac_urls
+            .into_iter()
+            .map(|pac_url| ProxySettings {
+                pac_url: Some(pac_url),
+                ..Default::default()
+            })
+            .collect::<Vec<ProxySettings>>();
+        (!proxy_settings.is_empty()).then_some(proxy_settings)
+    }
+
+    fn set(&self, _settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        self.sync()
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        self.sync()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(id: &str, pac_url: Option<&str>) -> SavedConnection {
+        SavedConnection {
+            path: dbus::Path::from("/org/freedesktop/NetworkManager/Settings/1"),
+            id: id.to_string(),
+            uuid: format!("{}-uuid", id),
+            proxy: ConnectionProxy {
+                auto: pac_url.is_some(),
+                pac_url: pac_url.map(String::from),
+                browser_only: false,
+            },
+        }
+    }
+
+    #[test]
+    fn test_desired_proxy() {
+        let pac_url = "http://wpad.corp/proxy.pac";
+        let office = ProxyProfile::new(
+            "office".to_string(),
+            ProxySettings {
+                pac_url: Some(pac_url.to_string()),
+                ..Default::default()
+            },
+            vec!["Office".to_string(), "Lab-uuid".to_string()],
+        );
+        let profiles = [office];
+
+        assert_eq!(
+            NetworkManager::desired_proxy(&connection("Lab", None), &profiles),
+            Some(ConnectionProxy {
+                auto: true,
+                pac_url: Some(pac_url.to_string()),
+                browser_only: false,
+            })
+        );
+        assert_eq!(
+            NetworkManager::desired_proxy(&connection("Office", Some(pac_url)), &profiles),
+            None
+        );
+        assert_eq!(
+            NetworkManager::desired_proxy(&connection("Cafe", Some(pac_url)), &profiles),
+            Some(ConnectionProxy::default())
+        );
+        assert_eq!(
+            NetworkManager::desired_proxy(
+                &connection("Home", Some("http://home/proxy.pac")),
+                &profiles
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{
+    proxy::ProxySettings,
+    target::{ini, proxy_url, settings_from_url, ProxyTarget},
+};
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// An `XferCommand` in `pacman.conf` that downloads through curl with
+/// `--proxy`, since pacman's own downloader only follows the environment,
+/// which `sudo` drops.
+pub struct Pacman {
+    config_file: PathBuf,
+}
+
+impl Pacman {
+    pub fn new(config_file: PathBuf) -> Self {
+        Self { config_file }
+    }
+
+    pub fn config_file() -> PathBuf {
+        PathBuf::from("/etc/pacman.conf")
+    }
+
+    fn generate_xfer_command(setting: &ProxySettings) -> String {
+        let mut command = format!(
+            "/usr/bin/curl -L -C - -f -o %o --proxy {}",
+            proxy_url(setting)
+        );
+        if !setting.no_proxy.is_empty() {
+            command.push_str(&format!(" --noproxy {}", setting.no_proxy.join(",")));
+        }
+        command.push_str(" %u");
+        command
+    }
+
+    /// Only an `XferCommand` passing `--proxy` is taken to be ours.
+    fn parse_xfer_command(command: &str) -> Option<ProxySettings> {
+        let mut arguments = command.split_whitespace();
+        let mut proxy = None;
+        let mut no_proxy = None;
+        while let Some(argument) = arguments.next() {
+            match argument {
+                "--proxy" => proxy = arguments.next(),
+                "--noproxy" => no_proxy = arguments.next(),
+                _ => continue,
+            }
+        }
+        settings_from_url(proxy?, no_proxy)
+    }
+
+    fn set_xfer_command(content: &str, setting: &ProxySettings) -> Result<String, Box<dyn Error>> {
+        if let Some(command) = ini::get_value(content, "options", "XferCommand") {
+            if Self::parse_xfer_command(&command).is_none() {
+                return Err("pacman.conf already sets its own XferCommand".into());
+            }
+        }
+        Ok(ini::set_value(
+            content,
+            "options",
+            "XferCommand",
+            &Self::generate_xfer_command(setting),
+        ))
+    }
+
+    fn unset_xfer_command(content: &str) -> String {
+        match ini::get_value(content, "options", "XferCommand") {
+            Some(command) if Self::parse_xfer_command(&command).is_some() => {
+                ini::remove_value(content, "options", "XferCommand")
+            }
+            _ => content.to_string(),
+        }
+    }
+}
+
+impl ProxyTarget for Pacman {
+    fn name(&self) -> &'static str {
+        "pacman"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.config_file).ok()?;
+        let command = ini::get_value(&content, "options", "XferCommand")?;
+        Self::parse_xfer_command(&command).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.config_file)?;
+        fs::write(
+            &self.config_file,
+            Self::set_xfer_command(&content, setting)?,
+        )?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.config_file) {
+            fs::write(&self.config_file, Self::unset_xfer_command(&content))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xfer_command() {
+        let content = "[options]\n\
+                       HoldPkg = pacman glibc\n\
+                       #XferCommand = /usr/bin/wget --passive-ftp -c -O %o %u\n\
+                       \n\
+                       [core]\n\
+                       Include = /etc/pacman.d/mirrorlist\n";
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            no_proxy: vec!["localhost".to_string(), ".corp".to_string()],
+            ..Default::default()
+        };
+
+        let updated = Pacman::set_xfer_command(content, &setting).unwrap();
+        assert_eq!(
+            ini::get_value(&updated, "options", "XferCommand").as_deref(),
+            Some(
+                "/usr/bin/curl -L -C - -f -o %o --proxy http://proxy.corp:3128 \
+                 --noproxy localhost,.corp %u"
+            )
+        );
+
+        let parsed = Pacman::parse_xfer_command(
+            &ini::get_value(&updated, "options", "XferCommand").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.no_proxy, ["localhost", ".corp"]);
+
+        assert_eq!(Pacman::unset_xfer_command(&updated), content);
+
+        let custom = ini::set_value(content, "options", "XferCommand", "/usr/bin/aria2c %u");
+        assert!(Pacman::set_xfer_command(&custom, &setting).is_err());
+        assert_eq!(Pacman::unset_xfer_command(&custom), custom);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{
+    proxy::ProxySettings,
+    target::{proxy_url, settings_from_url, ProxyTarget},
+};
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use toml_edit::{table, value, Array, DocumentMut, Item, Value};
+
+const PROXY_VARIABLES: [&str; 3] = ["http_proxy", "https_proxy", "no_proxy"];
+/// Trails the `[containers] http_proxy` that `set` added, telling it apart
+/// from one the user wrote.
+const TAG: &str = "# auto-proxy";
+
+/// The user's `containers.conf`: `[engine] env` sets the proxy for Podman
+/// itself, and `[containers] http_proxy` passes it on to containers.
+pub struct Podman {
+    config_file: PathBuf,
+}
+
+impl Podman {
+    pub fn new(config_file: PathBuf) -> Self {
+        Self { config_file }
+    }
+
+    pub fn config_file() -> PathBuf {
+        dirs::config_dir()
+            .unwrap()
+            .join("containers/containers.conf")
+    }
+
+    fn is_proxy_variable(entry: &str) -> bool {
+        entry.split_once('=').is_some_and(|(key, _)| {
+            PROXY_VARIABLES
+                .iter()
+                .any(|variable| key.eq_ignore_ascii_case(variable))
+        })
+    }
+
+    /// Returns `[engine] env` without the proxy variables, keeping anything
+    /// else the user put there.
+    fn other_env(document: &DocumentMut) -> Array {
+        document
+            .get("engine")
+            .and_then(|engine| engine.get("env"))
+            .and_then(Item::as_array)
+            .map(|env| {
+                env.iter()
+                    .filter(|entry| !entry.as_str().is_some_and(Self::is_proxy_variable))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn set_proxy(content: &str, setting: &ProxySettings) -> Result<String, Box<dyn Error>> {
+        let mut document = content.parse::<DocumentMut>()?;
+        let proxy = proxy_url(setting);
+
+        let mut env = Self::other_env(&document);
+        env.push(format!("http_proxy={}", proxy));
+        env.push(format!("https_proxy={}", proxy));
+        if !setting.no_proxy.is_empty() {
+            env.push(format!("no_proxy={}", setting.no_proxy.join(",")));
+        }
+
+        let engine = document.entry("engine").or_insert(table());
+        engine["env"] = value(env);
+        let containers = document.entry("containers").or_insert(table());
+        if containers.get("http_proxy").is_none() {
+            let mut http_proxy = Value::from(true);
+            http_proxy.decor_mut().set_suffix(format!(" {}", TAG));
+            containers["http_proxy"] = Item::Value(http_proxy);
+        }
+
+        Ok(document.to_string())
+    }
+
+    fn is_tagged(item: &Item) -> bool {
+        item.as_value()
+            .and_then(|value| value.decor().suffix())
+            .and_then(|suffix| suffix.as_str())
+            .is_some_and(|suffix| suffix.trim() == TAG)
+    }
+
+    /// Removes the proxy variables and the `http_proxy` that `set` added,
+    /// dropping tables left empty so that a file created by `set` goes back
+    /// to what it was.
+    fn unset_proxy(content: &str) -> Result<String, Box<dyn Error>> {
+        let mut document = content.parse::<DocumentMut>()?;
+
+        let env = Self::other_env(&document);
+        if let Some(engine) = document.get_mut("engine").and_then(Item::as_table_mut) {
+            if env.is_empty() {
+                engine.remove("env");
+            } else {
+                engine["env"] = value(env);
+            }
+        }
+        if let Some(containers) = document.get_mut("containers").and_then(Item::as_table_mut) {
+            if containers.get("http_proxy").is_some_and(Self::is_tagged) {
+                containers.remove("http_proxy");
+            }
+        }
+        for name in ["engine", "containers"] {
+            if document
+                .get(name)
+                .and_then(Item::as_table)
+                .is_some_and(|table| table.is_empty())
+            {
+                document.remove(name);
+            }
+        }
+
+        Ok(document.to_string())
+    }
+
+    fn get_proxy(content: &str) -> Option<ProxySettings> {
+        let document = content.parse::<DocumentMut>().ok()?;
+        let env = document.get("engine")?.get("env")?.as_array()?;
+        let variable = |name: &str| {
+            env.iter()
+                .filter_map(|entry| entry.as_str()?.split_once('='))
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        };
+
+        let proxy = variable("https_proxy").or_else(|| variable("http_proxy"))?;
+        settings_from_url(proxy, variable("no_proxy"))
+    }
+}
+
+impl ProxyTarget for Podman {
+    fn name(&self) -> &'static str {
+        "podman"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.config_file).ok()?;
+        Self::get_proxy(&content).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.config_file).unwrap_or_default();
+        if let Some(parent) = self.config_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let updated = Self::set_proxy(&content, setting)?;
+        if updated != content {
+            fs::write(&self.config_file, updated)?;
+        }
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.config_file) {
+            let updated = Self::unset_proxy(&content)?;
+            if updated != content {
+                fs::write(&self.config_file, updated)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_containers_conf() {
+        let content = "# Local overrides\n\
+                       [engine]\n\
+                       cgroup_manager = \"systemd\"\n\
+                       env = [\"TMPDIR=/var/tmp\"]\n";
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            no_proxy: vec!["localhost".to_string()],
+            ..Default::default()
+        };
+
+        let updated = Podman::set_proxy(content, &setting).unwrap();
+        let document = updated.parse::<DocumentMut>().unwrap();
+        assert_eq!(
+            document["engine"]["cgroup_manager"].as_str(),
+            Some("systemd")
+        );
+        assert_eq!(document["engine"]["env"].as_array().unwrap().len(), 4);
+        assert_eq!(document["containers"]["http_proxy"].as_bool(), Some(true));
+        assert!(updated.contains("http_proxy = true # auto-proxy\n"));
+
+        let parsed = Podman::get_proxy(&updated).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.no_proxy, ["localhost"]);
+
+        let restored = Podman::unset_proxy(&updated).unwrap();
+        assert!(Podman::get_proxy(&restored).is_none());
+        let document = restored.parse::<DocumentMut>().unwrap();
+        assert_eq!(document["engine"]["env"].as_array().unwrap().len(), 1);
+        assert!(document.get("containers").is_none());
+
+        assert_eq!(Podman::unset_proxy("").unwrap(), "");
+
+        let content = "[containers]\nhttp_proxy = false\n";
+        let updated = Podman::set_proxy(content, &setting).unwrap();
+        assert!(updated.contains("http_proxy = false\n"));
+        assert_eq!(Podman::unset_proxy(&updated).unwrap(), content);
+    }
+}
@@ -0,0 +1,38 @@
+//! Line-based editing of Java `.properties` files that keeps comments and
+//! unrelated keys untouched.
+
+fn key_value(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    if line.starts_with('#') || line.starts_with('!') {
+        return None;
+    }
+    line.split_once('=')
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+pub fn get_value<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content
+        .lines()
+        .filter_map(key_value)
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| value)
+}
+
+/// Removes every line setting one of `keys`.
+pub fn remove_keys(content: &str, keys: &[&str]) -> String {
+    content
+        .lines()
+        .filter(|line| key_value(line).is_none_or(|(name, _)| !keys.contains(&name)))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// Replaces the lines setting any of `keys` with `entries`, which are
+/// appended at the end of the file.
+pub fn replace_keys(content: &str, keys: &[&str], entries: &[(&str, String)]) -> String {
+    let mut content = remove_keys(content, keys);
+    for (key, value) in entries {
+        content.push_str(&format!("{}={}\n", key, value));
+    }
+    content
+}
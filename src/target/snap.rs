@@ -0,0 +1,252 @@
+use crate::{
+    proxy::ProxySettings,
+    target::{proxy_url, settings_from_url, ProxyTarget},
+};
+
+use serde_json::{json, Value};
+
+use std::error::Error;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+/// The system-wide proxy of snapd, the equivalent of
+/// `snap set system proxy.http=...`, read and written through the snapd
+/// REST API since snaps do not see our environment.
+pub struct Snap {
+    socket: PathBuf,
+}
+
+impl Snap {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket }
+    }
+
+    pub fn socket() -> PathBuf {
+        PathBuf::from("/run/snapd.socket")
+    }
+
+    fn generate_conf(setting: &ProxySettings) -> Value {
+        let proxy = proxy_url(setting);
+        json!({
+            "proxy.http": proxy,
+            "proxy.https": proxy,
+            "proxy.no-proxy": setting.no_proxy.join(","),
+        })
+    }
+
+    fn parse_conf(result: &Value) -> Option<ProxySettings> {
+        let proxy = &result["proxy"];
+        let url = ["https", "http"]
+            .into_iter()
+            .find_map(|key| proxy[key].as_str().filter(|url| !url.is_empty()))?;
+        settings_from_url(url, proxy["no-proxy"].as_str())
+    }
+
+    fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut decoded = Vec::new();
+        loop {
+            let line_end = body
+                .windows(2)
+                .position(|window| window == b"\r\n")
+                .ok_or("Truncated chunked response from snapd")?;
+            let size = std::str::from_utf8(&body[..line_end])?;
+            let size = usize::from_str_radix(size.split(';').next().unwrap_or("").trim(), 16)?;
+            if size == 0 {
+                return Ok(decoded);
+            }
+            let chunk = body
+                .get(line_end + 2..line_end + 2 + size)
+                .ok_or("Truncated chunked response from snapd")?;
+            decoded.extend_from_slice(chunk);
+            body = body.get(line_end + 4 + size..).unwrap_or_default();
+        }
+    }
+
+    /// Sends one request and returns the `result` of snapd's JSON reply.
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, Box<dyn Error>> {
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let mut stream = UnixStream::connect(&self.socket)?;
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\n\
+             Host: localhost\r\n\
+             Connection: close\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             \r\n\
+             {}",
+            method,
+            path,
+            body.len(),
+            body
+        )?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        let header_end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or("Malformed response from snapd")?;
+        let headers = String::from_utf8_lossy(&response[..header_end]).to_ascii_lowercase();
+        let content = &response[header_end + 4..];
+        let reply: Value = if headers.contains("transfer-encoding: chunked") {
+            serde_json::from_slice(&Self::decode_chunked(content)?)?
+        } else {
+            serde_json::from_slice(content)?
+        };
+
+        if reply["type"] == "error" {
+            let message = reply["result"]["message"]
+                .as_str()
+                .unwrap_or("unknown error");
+            return Err(format!("snapd: {}", message).into());
+        }
+        Ok(reply["result"].clone())
+    }
+}
+
+impl ProxyTarget for Snap {
+    fn name(&self) -> &'static str {
+        "snap"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let result = self
+            .request("GET", "/v2/snaps/system/conf?keys=proxy", None)
+            .ok()?;
+        Self::parse_conf(&result).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        self.request(
+            "PUT",
+            "/v2/snaps/system/conf",
+            Some(Self::generate_conf(setting)),
+        )?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        let conf = json!({
+            "proxy.http": null,
+            "proxy.https": null,
+            "proxy.no-proxy": null,
+        });
+        self.request("PUT", "/v2/snaps/system/conf", Some(conf))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    /// Answers one request per reply and hands back what it was sent.
+    fn serve(listener: UnixListener, replies: Vec<String>) -> thread::JoinHandle<Vec<String>> {
+        thread::spawn(move || {
+            let mut requests = Vec::new();
+            for reply in replies {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(length) = line.strip_prefix("Content-Length: ") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8(body).unwrap());
+                requests.push(request);
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\n\r\n{}",
+                    reply.len(),
+                    reply
+                )
+                .unwrap();
+            }
+            requests
+        })
+    }
+
+    #[test]
+    fn test_snapd_conf() {
+        let dir = env::temp_dir().join(format!("auto-proxy-snap-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("snapd.socket");
+        let _ = fs::remove_file(&socket);
+        let server = serve(
+            UnixListener::bind(&socket).unwrap(),
+            vec![
+                r#"{"type":"async","status-code":202,"status":"Accepted","change":"7"}"#.into(),
+                r#"{"type":"sync","status-code":200,"result":{"proxy":{
+                    "http":"http://proxy.corp:3128","https":"http://proxy.corp:3128",
+                    "no-proxy":"localhost,.corp"}}}"#
+                    .into(),
+                r#"{"type":"error","status-code":403,
+                    "result":{"message":"access denied","kind":"login-required"}}"#
+                    .into(),
+            ],
+        );
+
+        let snap = Snap::new(socket);
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            no_proxy: vec!["localhost".to_string(), ".corp".to_string()],
+            ..Default::default()
+        };
+        snap.set(vec![&setting]).unwrap();
+
+        let proxy_settings = snap.get().unwrap();
+        assert_eq!(proxy_settings[0].host, "proxy.corp");
+        assert_eq!(proxy_settings[0].no_proxy, ["localhost", ".corp"]);
+
+        assert_eq!(
+            snap.unset().unwrap_err().to_string(),
+            "snapd: access denied"
+        );
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("PUT /v2/snaps/system/conf HTTP/1.1\r\n"));
+        assert!(requests[0].ends_with(
+            r#"{"proxy.http":"http://proxy.corp:3128","proxy.https":"http://proxy.corp:3128","proxy.no-proxy":"localhost,.corp"}"#
+        ));
+        assert!(requests[1].starts_with("GET /v2/snaps/system/conf?keys=proxy HTTP/1.1\r\n"));
+        assert!(requests[2]
+            .ends_with(r#"{"proxy.http":null,"proxy.https":null,"proxy.no-proxy":null}"#));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_decode_chunked() {
+        let body = b"7\r\n{\"type\"\r\n8\r\n:\"sync\"}\r\n0\r\n\r\n";
+        assert_eq!(Snap::decode_chunked(body).unwrap(), b"{\"type\":\"sync\"}");
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    config::SshConfig,
+    proxy::{ProxyProtocol, ProxySettings},
+    target::{managed_block, ProxyTarget},
+};
+
+use std::env;
+use std::error::Error;
+use std::fs::{self, DirBuilder};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::PathBuf;
+
+/// A `Host` block in `~/.ssh/config` sending the configured hosts through the
+/// proxy, for networks that block outbound port 22. The tunnel is opened by
+/// `auto-proxy connect`, or by OpenBSD netcat when the config asks for it.
+pub struct Ssh {
+    config_file: PathBuf,
+    hosts: Vec<String>,
+    netcat: bool,
+    helper: PathBuf,
+}
+
+impl Ssh {
+    pub fn new(config_file: PathBuf, config: &SshConfig) -> Self {
+        Self {
+            config_file,
+            hosts: config.hosts.clone(),
+            netcat: config.netcat,
+            helper: env::current_exe().unwrap_or_else(|_| PathBuf::from("auto-proxy")),
+        }
+    }
+
+    pub fn config_file() -> PathBuf {
+        dirs::home_dir().unwrap().join(".ssh/config")
+    }
+
+    fn bypassed(host: &str, no_proxy: &[String]) -> bool {
+        no_proxy
+            .iter()
+            .any(|pattern| match pattern.strip_prefix('.') {
+                Some(domain) => host.ends_with(pattern) || host == domain,
+                None => host == pattern,
+            })
+    }
+
+    fn proxy_command(&self, setting: &ProxySettings) -> String {
+        if self.netcat {
+            let version = if setting.socks_only() { "5" } else { "connect" };
+            return format!(
+                "nc -X {} -x {}:{} %h %p",
+                version, setting.host, setting.port
+            );
+        }
+
+        let helper = self.helper.display().to_string();
+        if helper.contains(char::is_whitespace) {
+            format!("\"{}\" connect %h %p", helper)
+        } else {
+            format!("{} connect %h %p", helper)
+        }
+    }
+
+    /// The block's lines, or `None` when every host bypasses the proxy. It
+    /// goes at the top of the file, since SSH takes the first value it finds,
+    /// and ends with `Host *` so that options following it stay global.
+    fn generate_block(&self, setting: &ProxySettings) -> Option<String> {
+        let hosts = self
+            .hosts
+            .iter()
+            .filter(|host| !Self::bypassed(host, &setting.no_proxy))
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        if hosts.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "Host {}\n    ProxyCommand {}\nHost *\n",
+            hosts.join(" "),
+            self.proxy_command(setting)
+        ))
+    }
+
+    /// Reads the proxy back from a netcat `ProxyCommand`; the helper's
+    /// follows the active profile and names no proxy.
+    fn parse_block(block: &str) -> Option<ProxySettings> {
+        let command = block
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("ProxyCommand "))?;
+        let args = command.split_whitespace().collect::<Vec<&str>>();
+        let (host, port) = args.windows(2).find(|pair| pair[0] == "-x")?[1].rsplit_once(':')?;
+        let socks = args.windows(2).any(|pair| pair == ["-X", "5"]);
+        Some(ProxySettings {
+            host: host.to_string(),
+            port: port.to_string(),
+            protocols: if socks {
+                vec![ProxyProtocol::Socks]
+            } else {
+                Vec::new()
+            },
+            ..Default::default()
+        })
+    }
+}
+
+impl ProxyTarget for Ssh {
+    fn name(&self) -> &'static str {
+        "ssh"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.config_file).ok()?;
+        Self::parse_block(managed_block::get(&content, "#")?).map(|setting| vec![setting])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let authenticated = settings.first().is_some_and(|setting| {
+            setting
+                .auth
+                .as_ref()
+                .is_some_and(|auth| !auth.username.is_empty())
+        });
+        if self.netcat && authenticated {
+            return Err("netcat cannot pass proxy credentials; turn off `ssh.netcat`".into());
+        }
+
+        let block = match settings
+            .first()
+            .and_then(|setting| self.generate_block(setting))
+        {
+            Some(block) => block,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.config_file).unwrap_or_default();
+        if let Some(parent) = self.config_file.parent() {
+            DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(parent)?;
+        }
+        fs::write(
+            &self.config_file,
+            managed_block::prepend(&content, "#", &block),
+        )?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.config_file) {
+            fs::write(&self.config_file, managed_block::remove(&content, "#"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::ProxyAuth;
+
+    #[test]
+    fn test_host_block() {
+        let mut ssh = Ssh {
+            config_file: PathBuf::from("/nonexistent/config"),
+            hosts: vec!["github.com".to_string(), "gitlab.corp".to_string()],
+            netcat: false,
+            helper: PathBuf::from("/usr/bin/auto-proxy"),
+        };
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "1080".to_string(),
+            protocols: vec![ProxyProtocol::Socks],
+            no_proxy: vec![".corp".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            ssh.generate_block(&setting).unwrap(),
+            "Host github.com\n    ProxyCommand /usr/bin/auto-proxy connect %h %p\nHost *\n"
+        );
+
+        ssh.netcat = true;
+        let block = ssh.generate_block(&setting).unwrap();
+        assert_eq!(
+            block,
+            "Host github.com\n    ProxyCommand nc -X 5 -x proxy.corp:1080 %h %p\nHost *\n"
+        );
+        let parsed = Ssh::parse_block(&block).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.protocols, [ProxyProtocol::Socks]);
+
+        let authenticated = ProxySettings {
+            auth: Some(ProxyAuth::new("alice".to_string(), "s3cret".to_string())),
+            ..Default::default()
+        };
+        assert!(ssh.set(vec![&authenticated]).is_err());
+
+        ssh.hosts = vec!["gitlab.corp".to_string()];
+        assert!(ssh.generate_block(&setting).is_none());
+    }
+}
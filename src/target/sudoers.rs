@@ -0,0 +1,142 @@
+use crate::{proxy::ProxySettings, target::ProxyTarget};
+
+use std::error::Error;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+const MANAGED_HEADER: &str = "# Managed by auto-proxy";
+
+/// An `env_keep` rule so that `sudo` passes the proxy variables through.
+/// The rule does not depend on the profile; it is installed on `set` and
+/// removed on `unset`.
+pub struct Sudoers {
+    sudoers_file: PathBuf,
+    visudo: PathBuf,
+}
+
+impl Sudoers {
+    pub fn new(sudoers_file: PathBuf, visudo: PathBuf) -> Self {
+        Self {
+            sudoers_file,
+            visudo,
+        }
+    }
+
+    pub fn sudoers_file() -> PathBuf {
+        PathBuf::from("/etc/sudoers.d/auto-proxy")
+    }
+
+    fn generate_sudoers_content() -> String {
+        let variables = [
+            "http_proxy",
+            "https_proxy",
+            "ftp_proxy",
+            "all_proxy",
+            "no_proxy",
+        ];
+        let upper = variables.map(|variable| variable.to_ascii_uppercase());
+        format!(
+            "{}\nDefaults env_keep += \"{} {}\"\n",
+            MANAGED_HEADER,
+            variables.join(" "),
+            upper.join(" ")
+        )
+    }
+
+    fn is_managed(&self) -> bool {
+        fs::read_to_string(&self.sudoers_file)
+            .is_ok_and(|content| content.starts_with(MANAGED_HEADER))
+    }
+
+    /// Writes the rule to a file sudo ignores, because its name contains a
+    /// dot, and only moves it into place once `visudo -cf` accepts it.
+    fn install(&self, content: &str) -> Result<(), Box<dyn Error>> {
+        let staging = self.sudoers_file.with_file_name(".auto-proxy.tmp");
+        let _ = fs::remove_file(&staging);
+        fs::write(&staging, content)?;
+        fs::set_permissions(&staging, fs::Permissions::from_mode(0o440))?;
+
+        let output = Command::new(&self.visudo).arg("-cf").arg(&staging).output();
+        let result = match output {
+            Ok(output) if output.status.success() => {
+                fs::rename(&staging, &self.sudoers_file)?;
+                return Ok(());
+            }
+            Ok(output) => format!(
+                "visudo rejected the rule: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(err) => format!("Cannot validate the rule with visudo: {}", err),
+        };
+
+        fs::remove_file(&staging)?;
+        Err(result.into())
+    }
+}
+
+impl ProxyTarget for Sudoers {
+    fn name(&self) -> &'static str {
+        "sudoers"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        None
+    }
+
+    fn set(&self, _settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        if self.sudoers_file.exists() && !self.is_managed() {
+            return Err(format!(
+                "{} is not managed by auto-proxy",
+                self.sudoers_file.display()
+            )
+            .into());
+        }
+        self.install(&Self::generate_sudoers_content())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if self.is_managed() {
+            fs::remove_file(&self.sudoers_file)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_install_is_validated() {
+        let dir = env::temp_dir().join(format!("auto-proxy-sudoers-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // `false` and `true` stand in for a visudo rejecting and accepting
+        // the rule.
+        let sudoers_file = dir.join("auto-proxy");
+
+        let rejecting = Sudoers::new(sudoers_file.clone(), "false".into());
+        assert!(rejecting.set(Vec::new()).is_err());
+        assert!(!sudoers_file.exists());
+        assert!(!dir.join(".auto-proxy.tmp").exists());
+
+        let accepting = Sudoers::new(sudoers_file.clone(), "true".into());
+        accepting.set(Vec::new()).unwrap();
+        assert_eq!(
+            fs::read_to_string(&sudoers_file).unwrap(),
+            "# Managed by auto-proxy\n\
+             Defaults env_keep += \"http_proxy https_proxy ftp_proxy all_proxy no_proxy \
+             HTTP_PROXY HTTPS_PROXY FTP_PROXY ALL_PROXY NO_PROXY\"\n"
+        );
+        assert_eq!(
+            fs::metadata(&sudoers_file).unwrap().permissions().mode() & 0o777,
+            0o440
+        );
+
+        accepting.unset().unwrap();
+        assert!(!sudoers_file.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
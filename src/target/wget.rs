@@ -0,0 +1,155 @@
+use crate::{
+    proxy::ProxySettings,
+    target::{managed_block, proxy_url, settings_from_url, ProxyTarget},
+};
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// A managed block in `~/.wgetrc`, which wget reads even when the proxy
+/// variables are not exported, such as under cron or `env -i`. wget has no
+/// SOCKS support, so a SOCKS-only profile leaves it without a proxy.
+pub struct Wget {
+    rc_file: PathBuf,
+    skipped_socks: Cell<bool>,
+}
+
+impl Wget {
+    pub fn new(rc_file: PathBuf) -> Self {
+        Self {
+            rc_file,
+            skipped_socks: Cell::new(false),
+        }
+    }
+
+    pub fn rc_file() -> PathBuf {
+        dirs::home_dir().unwrap().join(".wgetrc")
+    }
+
+    fn generate_wgetrc_content(setting: &ProxySettings) -> String {
+        let proxy = proxy_url(setting);
+        let mut content = format!(
+            "use_proxy = on\n\
+             http_proxy = {}\n\
+             https_proxy = {}\n",
+            proxy, proxy
+        );
+        if !setting.no_proxy.is_empty() {
+            content.push_str(&format!("no_proxy = {}\n", setting.no_proxy.join(",")));
+        }
+        content
+    }
+
+    fn parse_wgetrc_content(content: &str) -> Option<ProxySettings> {
+        let command = |name: &str| {
+            content.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == name).then(|| value.trim())
+            })
+        };
+        if command("use_proxy") == Some("off") {
+            return None;
+        }
+
+        let proxy = command("https_proxy").or_else(|| command("http_proxy"))?;
+        settings_from_url(proxy, command("no_proxy"))
+    }
+}
+
+impl ProxyTarget for Wget {
+    fn name(&self) -> &'static str {
+        "wget"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.rc_file).ok()?;
+        Self::parse_wgetrc_content(managed_block::get(&content, "#")?)
+            .map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        self.skipped_socks
+            .set(settings.first().is_some_and(|setting| setting.socks_only()));
+        let setting = match settings.first() {
+            Some(setting) if !setting.socks_only() => setting,
+            _ => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.rc_file).unwrap_or_default();
+        fs::write(
+            &self.rc_file,
+            managed_block::replace(&content, "#", &Self::generate_wgetrc_content(setting)),
+        )?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.rc_file) {
+            fs::write(&self.rc_file, managed_block::remove(&content, "#"))?;
+        }
+        Ok(())
+    }
+
+    fn follow_up(&self) -> Option<String> {
+        self.skipped_socks
+            .get()
+            .then(|| "wget cannot use a SOCKS proxy, so it was left without one".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::ProxyProtocol;
+    use std::env;
+
+    #[test]
+    fn test_wgetrc_content() {
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            no_proxy: vec!["localhost".to_string()],
+            ..Default::default()
+        };
+
+        let content = Wget::generate_wgetrc_content(&setting);
+        assert_eq!(
+            content,
+            "use_proxy = on\n\
+             http_proxy = http://proxy.corp:3128\n\
+             https_proxy = http://proxy.corp:3128\n\
+             no_proxy = localhost\n"
+        );
+
+        let parsed = Wget::parse_wgetrc_content(&content).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.port, "3128");
+        assert_eq!(parsed.no_proxy, ["localhost"]);
+        assert!(Wget::parse_wgetrc_content("use_proxy = off\n").is_none());
+    }
+
+    #[test]
+    fn test_socks_only_profile() {
+        let path = env::temp_dir().join(format!("auto-proxy-wgetrc-{}", std::process::id()));
+        let wget = Wget::new(path.clone());
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            ..Default::default()
+        };
+        wget.set(vec![&setting]).unwrap();
+        assert!(wget.get().is_some());
+        assert!(wget.follow_up().is_none());
+
+        let socks = ProxySettings {
+            protocols: vec![ProxyProtocol::Socks],
+            ..setting
+        };
+        wget.set(vec![&socks]).unwrap();
+        assert!(wget.get().is_none());
+        assert!(wget.follow_up().is_some());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{
+    proxy::{ProxyAuth, ProxySettings},
+    target::{ini, settings_from_url, ProxyTarget},
+};
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+const KEYS: [&str; 3] = ["proxy", "proxy_username", "proxy_password"];
+
+/// The `[main]` proxy options of `/etc/yum.conf`.
+pub struct Yum {
+    config_file: PathBuf,
+}
+
+impl Yum {
+    pub fn new(config_file: PathBuf) -> Self {
+        Self { config_file }
+    }
+
+    pub fn config_file() -> PathBuf {
+        PathBuf::from("/etc/yum.conf")
+    }
+
+    /// Sets `proxy`, with the credentials in their own options rather than
+    /// in the URL, which yum does not decode.
+    fn set_main(content: &str, setting: &ProxySettings) -> String {
+        let mut content = ini::set_value(
+            content,
+            "main",
+            "proxy",
+            &format!("http://{}:{}", setting.host, setting.port),
+        );
+        match setting
+            .auth
+            .as_ref()
+            .filter(|auth| !auth.username.is_empty())
+        {
+            Some(auth) => {
+                content = ini::set_value(&content, "main", "proxy_username", &auth.username);
+                content = ini::set_value(&content, "main", "proxy_password", &auth.password);
+            }
+            None => {
+                content = ini::remove_value(&content, "main", "proxy_username");
+                content = ini::remove_value(&content, "main", "proxy_password");
+            }
+        }
+        content
+    }
+
+    fn unset_main(content: &str) -> String {
+        KEYS.iter().fold(content.to_string(), |content, key| {
+            ini::remove_value(&content, "main", key)
+        })
+    }
+
+    fn get_main(content: &str) -> Option<ProxySettings> {
+        let proxy = ini::get_value(content, "main", "proxy")?;
+        let mut proxy_settings = settings_from_url(&proxy, None)?;
+        if let Some(username) = ini::get_value(content, "main", "proxy_username") {
+            let password = ini::get_value(content, "main", "proxy_password").unwrap_or_default();
+            proxy_settings.auth = Some(ProxyAuth::new(username, password));
+        }
+        Some(proxy_settings)
+    }
+}
+
+impl ProxyTarget for Yum {
+    fn name(&self) -> &'static str {
+        "yum"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.config_file).ok()?;
+        Self::get_main(&content).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.config_file).unwrap_or_default();
+        fs::write(&self.config_file, Self::set_main(&content, setting))?;
+        Ok(())
+    }
+
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.config_file) {
+            fs::write(&self.config_file, Self::unset_main(&content))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_main_section() {
+        let content = "[main]\ngpgcheck=1\ninstallonly_limit=3\n";
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            auth: Some(ProxyAuth::new("jane".to_string(), "secret".to_string())),
+            ..Default::default()
+        };
+
+        let updated = Yum::set_main(content, &setting);
+        assert_eq!(
+            updated,
+            "[main]\n\
+             gpgcheck=1\n\
+             installonly_limit=3\n\
+             proxy = http://proxy.corp:3128\n\
+             proxy_username = jane\n\
+             proxy_password = secret\n"
+        );
+
+        let parsed = Yum::get_main(&updated).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.auth.unwrap().password, "secret");
+
+        assert_eq!(Yum::unset_main(&updated), content);
+    }
+}
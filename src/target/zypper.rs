@@ -0,0 +1,154 @@
+use crate::{
+    proxy::ProxySettings,
+    target::{proxy_url, settings_from_url, ProxyTarget},
+};
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// `/etc/sysconfig/proxy`, which zypper and YaST read on SUSE systems.
+pub struct Zypper {
+    config_file: PathBuf,
+}
+
+impl Zypper {
+    pub fn new(config_file: PathBuf) -> Self {
+        Self { config_file }
+    }
+
+    pub fn config_file() -> PathBuf {
+        PathBuf::from("/etc/sysconfig/proxy")
+    }
+
+    fn variable<'a>(content: &'a str, name: &str) -> Option<&'a str> {
+        content.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == name).then(|| value.trim().trim_matches('"'))
+        })
+    }
+
+    /// Sets each variable in place, keeping the comments that document
+    /// them, and appends the ones the file lacks.
+    fn set_variables(content: &str, variables: &[(&str, String)]) -> String {
+        let mut remaining = variables.to_vec();
+        let mut result = String::new();
+
+        for line in content.lines() {
+            let name = line.split_once('=').map(|(key, _)| key.trim());
+            match remaining
+                .iter()
+                .position(|(variable, _)| Some(*variable) == name)
+            {
+                Some(index) => {
+                    let (variable, value) = remaining.remove(index);
+                    result.push_str(&format!("{}=\"{}\"\n", variable, value));
+                }
+                None => {
+                    result.push_str(line);
+                    result.push('\n');
+                }
+            }
+        }
+        for (variable, value) in remaining {
+            result.push_str(&format!("{}=\"{}\"\n", variable, value));
+        }
+        result
+    }
+
+    fn generate_variables(setting: &ProxySettings) -> Vec<(&'static str, String)> {
+        let proxy = proxy_url(setting);
+        vec![
+            ("PROXY_ENABLED", "yes".to_string()),
+            ("HTTP_PROXY", proxy.clone()),
+            ("HTTPS_PROXY", proxy.clone()),
+            ("FTP_PROXY", proxy),
+            ("NO_PROXY", setting.no_proxy.join(", ")),
+        ]
+    }
+
+    fn parse_variables(content: &str) -> Option<ProxySettings> {
+        if Self::variable(content, "PROXY_ENABLED") != Some("yes") {
+            return None;
+        }
+        let proxy = Self::variable(content, "HTTPS_PROXY")
+            .filter(|proxy| !proxy.is_empty())
+            .or_else(|| Self::variable(content, "HTTP_PROXY"))?;
+        settings_from_url(proxy, Self::variable(content, "NO_PROXY"))
+    }
+}
+
+impl ProxyTarget for Zypper {
+    fn name(&self) -> &'static str {
+        "zypper"
+    }
+
+    fn get(&self) -> Option<Vec<ProxySettings>> {
+        let content = fs::read_to_string(&self.config_file).ok()?;
+        Self::parse_variables(&content).map(|proxy_settings| vec![proxy_settings])
+    }
+
+    fn set(&self, settings: Vec<&ProxySettings>) -> Result<(), Box<dyn Error>> {
+        let setting = match settings.first() {
+            Some(setting) => setting,
+            None => return self.unset(),
+        };
+
+        let content = fs::read_to_string(&self.config_file).unwrap_or_default();
+        fs::write(
+            &self.config_file,
+            Self::set_variables(&content, &Self::generate_variables(setting)),
+        )?;
+        Ok(())
+    }
+
+    /// Disables the proxy the way YaST does, leaving the variables empty.
+    fn unset(&self) -> Result<(), Box<dyn Error>> {
+        if let Ok(content) = fs::read_to_string(&self.config_file) {
+            let variables = [
+                ("PROXY_ENABLED", "no".to_string()),
+                ("HTTP_PROXY", String::new()),
+                ("HTTPS_PROXY", String::new()),
+                ("FTP_PROXY", String::new()),
+            ];
+            fs::write(&self.config_file, Self::set_variables(&content, &variables))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sysconfig_proxy() {
+        let content = "## Type:\tyesno\n\
+                       PROXY_ENABLED=\"no\"\n\
+                       HTTP_PROXY=\"\"\n\
+                       HTTPS_PROXY=\"\"\n\
+                       NO_PROXY=\"localhost, 127.0.0.1\"\n";
+        let setting = ProxySettings {
+            host: "proxy.corp".to_string(),
+            port: "3128".to_string(),
+            no_proxy: vec!["localhost".to_string(), ".corp".to_string()],
+            ..Default::default()
+        };
+
+        let updated = Zypper::set_variables(content, &Zypper::generate_variables(&setting));
+        assert_eq!(
+            updated,
+            "## Type:\tyesno\n\
+             PROXY_ENABLED=\"yes\"\n\
+             HTTP_PROXY=\"http://proxy.corp:3128\"\n\
+             HTTPS_PROXY=\"http://proxy.corp:3128\"\n\
+             NO_PROXY=\"localhost, .corp\"\n\
+             FTP_PROXY=\"http://proxy.corp:3128\"\n"
+        );
+
+        let parsed = Zypper::parse_variables(&updated).unwrap();
+        assert_eq!(parsed.host, "proxy.corp");
+        assert_eq!(parsed.no_proxy, ["localhost", ".corp"]);
+        assert!(Zypper::parse_variables(content).is_none());
+    }
+}